@@ -20,7 +20,7 @@ try {
   const spendPublicKeyPoint = secp256k1.Point.fromHex(alicePublicKeys[1]);
 
   // Generate Encrypted address by Alice's public keys
-  const { ephemeralPublicKey, owner } = await generateEncyptedAddress(scanPublicKeyPoint, spendPublicKeyPoint);
+  const { ephemeralPublicKey, owner, viewTag } = await generateEncyptedAddress(scanPublicKeyPoint, spendPublicKeyPoint);
 
   // Compress ephemeral public key
   let ephemeralPublicKeyBytes = ephemeralPublicKey.toRawBytes(true);
@@ -48,7 +48,7 @@ try {
         refund: nconf.get('refund'),
         function: 'mint',
         selector: '0xcfdd9aa2',
-        contract_params: [{ 'accountid': owner }, { 'string': bytesToHex(ephemeralPublicKeyBytes) }]
+        contract_params: [{ 'accountid': owner }, { 'string': bytesToHex(ephemeralPublicKeyBytes) }, { 'viewtag': viewTag }]
       },
       headers: {
         'Content-Type': 'application/json',
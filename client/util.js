@@ -58,7 +58,10 @@ export async function generateEncyptedAddress(scanPublicKeyPoint, spendPublicKey
   // Convert to substrate address format
   const owner = crypto.encodeAddress(PToU8a);
 
-  return { ephemeralPublicKey, owner };
+  // The first byte of the shared secret lets the receiver skip most announcements
+  const viewTag = sharedSecret[0];
+
+  return { ephemeralPublicKey, owner, viewTag };
 }
 
 /**
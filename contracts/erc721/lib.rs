@@ -11,12 +11,55 @@
 //!
 //! This contract demonstrates how to build non-fungible tokens with an anonymous owner using ink!.
 //!
+//! ## PSP34
+//!
+//! The contract implements the [PSP34](https://github.com/w3f/PSPs/blob/master/PSPs/psp-34.md) standard
+//! and its Metadata extension, so tokens are identified by `Id::U32` ids.
+//!
+//! The PSP34 `approve` and `transfer` messages are authorized by the caller. Stealth owners never sign
+//! a transaction themselves, so their tokens are managed by the signature based variants instead,
+//! `stealth_approve`, `stealth_transfer`, `transfer_from`, `revoke_approval`, `set_approval_for_all` and `burn`,
+//! which can be submitted by anyone, e.g. the relayer.
+//!
+//! ## Metadata
+//!
+//! The URI of a token is derived from the base URI, optionally followed by a `.json` suffix,
+//! unless the admin has set a specific URI for that token. The admin, i.e. the account that
+//! instantiated the contract, can also store key/value attributes of the collection and tokens,
+//! which are returned by the PSP34 `get_attribute` query. The metadata can be changed until
+//! the admin calls `freeze_metadata`.
+//!
+//! ### Hidden Metadata
+//!
+//! Revealing the metadata of anonymous mints right away links the mint order to the rarity of the tokens.
+//! A contract instantiated by `new_hidden` commits to the Keccak-256 hash of the base URI followed by a salt,
+//! and returns a placeholder URI for every token until the admin calls `reveal(base_uri, salt)`
+//! with a base URI and salt matching the hash.
+//!
+//! ## Administration
+//!
+//! The admin can hand over the role in two steps, by `transfer_ownership` and `accept_ownership`
+//! of the new admin. The admin can also upgrade the contract by `set_code_hash`. Storage fields are only
+//! ever appended, and the storage version tells new code which fields need to be initialized, so existing
+//! tokens are kept across upgrades.
+//!
+//! ## Royalties
+//!
+//! `royalty_info(id, sale_price)` returns the royalty receiver and amount of a sale, following ERC-2981.
+//! The admin sets a default royalty for the collection and can override it per token, with fractions given
+//! in basis points. The receiver can be a stealth address derived from the creator's registered keys,
+//! so royalty income cannot be linked to the creator. Setting a royalty emits a `RoyaltySet` event,
+//! and for a stealth receiver an `Announcement` of its ephemeral public key and view tag, like a mint,
+//! under the token id or `0` for the default royalty.
+//!
 //! ## Error Handling
 //!
 //! Any function that modifies the state returns a `Result` type and does not changes the state
 //! if the `Error` occurs.
 //! The errors are defined as an `enum` type. Any other error or invariant violation
 //! triggers a panic and therefore rolls back the transaction.
+//! Malformed hex strings, ephemeral public keys and signatures are reported as errors
+//! rather than panics.
 //!
 //! ## Token Management
 //!
@@ -24,13 +67,25 @@
 //! A token can be created, transferred, or destroyed.
 //!
 //! Token owners can assign other accounts for transferring specific tokens on their behalf.
+//! A new approval replaces the previous one, and `revoke_approval` clears it.
 //! It is also possible to authorize an operator (higher rights) for another account to handle tokens.
+//! An operator is authorized by calling `set_approval_for_all` with a signature of the owner,
+//! and can transfer or approve every token of the owner.
 //!
 //! ### Token Creation
 //!
-//! Token creation start by calling the `mint(&mut self, owner: AccountId, id: u32)` function.
-//! The token owner becomes the owner address that is inputted by the function caller. The token ID needs to be specified
-//! as the argument on this function call.
+//! Token creation start by calling the `mint(&mut self, owner: AccountId, ephemeral_public_key: String, view_tag: u8)` function.
+//! The token owner becomes the owner address that is inputted by the function caller. The token ID is assigned
+//! incrementally by the contract.
+//!
+//! The ephemeral public key given on mint, transfer and approve must be a hex encoded compressed
+//! secp256k1 point, i.e. 33 bytes starting with `0x02` or `0x03`.
+//!
+//! Mint, transfer and approve also emit an `Announcement` event with the stealth address,
+//! the ephemeral public key and a view tag, the first byte of the shared secret hash.
+//! A recipient computes the shared secret hash with its scan private key, and only derives the stealth
+//! address of announcements whose view tag matches. The view tag is not covered by the signature,
+//! so a wrong view tag only makes the recipient miss the announcement, and a full scan still finds the token.
 //!
 //! ### Token Transfer
 //!
@@ -39,11 +94,37 @@
 //! - The approved address of a token
 //! - An authorized operator of the current owner of a token
 //!
-//! The token owner can transfer a token by calling the `transfer` functions..
-//! An approved address can make a token transfer by calling the `transfer` function.
+//! The token owner can transfer a token by calling the `stealth_transfer` or PSP34 `transfer` functions.
+//! An approved address can make a token transfer by calling the `transfer_from` function.
+//! The PSP34 `transfer` and the `safe_stealth_transfer`, `safe_transfer_from` and `safe_transfer` variants
+//! make sure a receiving contract can handle the token: if `to` is a contract, its
+//! `PSP34Receiver::before_received` message, as in openbrush, must return `Ok(())`, otherwise the transfer
+//! fails. Transfers to wallets and stealth addresses are not affected.
+//! Several tokens of the same or different signers can be transferred at once by `transfer_batch`,
+//! which moves either all tokens or none. Each transfer is signed by the owner, the approved account
+//! or an operator of the token.
 //! Operators can transfer tokens on another account's behalf or can approve a token transfer
 //! for a different account.
 //!
+//! ### Token Enumeration
+//!
+//! Existing tokens can be enumerated by `token_by_index`. Wallets scanning for tokens owned by their
+//! stealth addresses can fetch owners, ephemeral public keys and nonces in pages by `tokens(start, count)`,
+//! and the owners of known tokens by `owners_of`.
+//!
+//! ### Token History
+//!
+//! Every mint and transfer is appended to the ownership history of the token, which is returned by
+//! `token_history(id, start, count)`. Each entry holds the token nonce, the owner, its ephemeral public key
+//! and the block number, so previous stealth owners can prove the provenance of anonymous tokens.
+//!
+//! ### Token Locking
+//!
+//! Tokens used as anonymous credentials must not be transferable. A contract instantiated by `new_soulbound`
+//! locks every token, and the admin can lock single tokens of other collections by `set_locked`.
+//! Locked tokens cannot be transferred or approved, which fails with `Error::TokenLocked`, but can still be burned.
+//! The `locked` query and the `Locked` and `Unlocked` events follow ERC-5192.
+//!
 //! ### Token Removal
 //!
 //! Tokens can be destroyed by burning them. Only the token owner is allowed to burn a token.
@@ -53,8 +134,11 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 use ink_lang as ink;
 
+pub mod traits;
+
 #[ink::contract]
 pub mod erc721 {
+  use crate::traits::{Id, PSP34Error, PSP34Metadata, PSP34ReceiverError, PSP34};
   use ink_prelude::{string::String, string::ToString, vec::Vec};
 
   use ink_storage::{traits::SpreadAllocate, Mapping};
@@ -64,6 +148,32 @@ pub mod erc721 {
   /// A token ID.
   pub type TokenId = u32;
 
+  /// A compressed secp256k1 ephemeral public key.
+  pub type EphemeralPublicKey = [u8; 33];
+
+  /// An entry of the token ownership history:
+  /// the token nonce, the owner, its ephemeral public key and the block number of the change.
+  pub type HistoryEntry = (u32, AccountId, Option<EphemeralPublicKey>, BlockNumber);
+
+  /// The version of the storage layout written by this code.
+  ///
+  /// New fields are only appended to the end of the storage struct, so an upgraded contract keeps the
+  /// existing tokens. Bump the version whenever fields are added whose default value is not correct
+  /// for existing contracts, and initialize them in a `migrate` message of the new code which fails
+  /// unless the stored version is older. Version 1 is the first versioned layout, so there is nothing
+  /// to migrate yet, and contracts deployed before it cannot be upgraded.
+  const STORAGE_VERSION: u32 = 1;
+
+  /// The denominator of royalty fractions, i.e. fractions are given in basis points.
+  const ROYALTY_DENOMINATOR: u16 = 10_000;
+
+  /// The id announcing the receiver of the default royalty. Token ids start at 1, so it is not
+  /// the id of a token.
+  const DEFAULT_ROYALTY_ID: TokenId = 0;
+
+  /// The maximum number of items returned by the paginated queries.
+  const MAX_ITEMS_PER_QUERY: u32 = 100;
+
   #[ink(storage)]
   #[derive(Default, SpreadAllocate)]
   pub struct Erc721 {
@@ -72,7 +182,7 @@ pub mod erc721 {
     /// Mapping from token to owner.
     token_owner: Mapping<TokenId, AccountId>,
     /// Mapping from token to ephemeral public key.
-    token_ephemeral: Mapping<TokenId, String>,
+    token_ephemeral: Mapping<TokenId, EphemeralPublicKey>,
     /// Mapping from owner to number of owned token.
     owned_tokens_count: Mapping<AccountId, u32>,
     /// Mapping from token to approvals users.
@@ -81,6 +191,48 @@ pub mod erc721 {
     token_nonce: Mapping<TokenId, u32>,
     /// Token Base URI
     base_uri: String,
+    /// Mapping from owner to operator approvals.
+    operator_approvals: Mapping<(AccountId, AccountId), ()>,
+    /// Mapping from owner to nonce, which is an incrementing integer added to a hashed operator approval.
+    operator_nonce: Mapping<AccountId, u32>,
+    /// The account allowed to manage the metadata.
+    admin: AccountId,
+    /// Mapping from token to URI, which overrides the URI derived from the base URI.
+    token_uris: Mapping<TokenId, String>,
+    /// Whether the `.json` suffix is appended to the URI derived from the base URI.
+    uri_suffix: bool,
+    /// Whether the metadata can no longer be changed.
+    metadata_frozen: bool,
+    /// Mapping from collection or token `Id` and key to attribute value.
+    attributes: Mapping<(Id, Vec<u8>), Vec<u8>>,
+    /// Whether the token URIs are revealed.
+    revealed: bool,
+    /// The URI returned for every token until the metadata is revealed.
+    placeholder_uri: String,
+    /// Keccak-256 hash of the hidden base URI followed by the salt.
+    provenance_hash: Option<[u8; 32]>,
+    /// The last minted token ID, so IDs of burned tokens are never reused.
+    last_token_id: TokenId,
+    /// Mapping from index to token, for every existing token.
+    all_tokens: Mapping<u32, TokenId>,
+    /// Mapping from token to its index in `all_tokens`.
+    all_tokens_index: Mapping<TokenId, u32>,
+    /// Mapping from token and index to the ownership history entry.
+    token_history: Mapping<(TokenId, u32), HistoryEntry>,
+    /// Mapping from token to the number of ownership history entries.
+    token_history_len: Mapping<TokenId, u32>,
+    /// The default royalty receiver and fraction in basis points of the sale price.
+    default_royalty: (AccountId, u16),
+    /// Mapping from token to royalty receiver and fraction, which overrides the default royalty.
+    token_royalties: Mapping<TokenId, (AccountId, u16)>,
+    /// Whether every token is locked, i.e. the collection is soulbound.
+    soulbound: bool,
+    /// Mapping from token to lock, for tokens locked individually.
+    locked_tokens: Mapping<TokenId, ()>,
+    /// The account proposed as new admin, until it accepts the ownership.
+    pending_admin: Option<AccountId>,
+    /// The version of the storage layout, see `STORAGE_VERSION`.
+    storage_version: u32,
   }
 
   #[derive(Encode, Decode, Debug, PartialEq, Eq, Copy, Clone)]
@@ -98,6 +250,58 @@ pub mod erc721 {
     /// A hex string's length needs to be even, as two digits correspond to
     /// one byte.
     OddLength,
+    /// The signature is not 65 bytes long, or the signer cannot be recovered from it.
+    InvalidSignature,
+    /// The ephemeral public key is not a 33 bytes compressed point with a `0x02` or `0x03` prefix.
+    InvalidEphemeralKey,
+    /// The caller is not the admin of the contract.
+    NotAdmin,
+    /// The metadata has been frozen and cannot be changed.
+    MetadataFrozen,
+    /// The metadata has not been revealed yet.
+    NotRevealed,
+    /// The metadata has already been revealed.
+    AlreadyRevealed,
+    /// The revealed base URI and salt do not match the provenance hash.
+    InvalidProvenance,
+    /// The receiving contract did not acknowledge the safe transfer.
+    SafeTransferCheckFailed,
+    /// The royalty fraction exceeds 10000 basis points.
+    InvalidRoyalty,
+    /// The token is locked and cannot be transferred or approved.
+    TokenLocked,
+    /// The caller is not the pending admin.
+    NotPendingAdmin,
+    /// The code hash of the contract could not be replaced.
+    UpgradeFailed,
+  }
+
+  impl From<Error> for PSP34Error {
+    fn from(error: Error) -> Self {
+      let custom = match error {
+        Error::NotOwner | Error::NotApproved | Error::NotAllowed => return PSP34Error::NotApproved,
+        Error::TokenNotFound => return PSP34Error::TokenNotExists,
+        Error::SafeTransferCheckFailed => {
+          return PSP34Error::SafeTransferCheckFailed(String::from("receiver rejected the token"))
+        }
+        Error::CannotInsert => "CannotInsert",
+        Error::CannotFetchValue => "CannotFetchValue",
+        Error::InvalidHexCharacter => "InvalidHexCharacter",
+        Error::OddLength => "OddLength",
+        Error::InvalidSignature => "InvalidSignature",
+        Error::InvalidEphemeralKey => "InvalidEphemeralKey",
+        Error::NotAdmin => "NotAdmin",
+        Error::MetadataFrozen => "MetadataFrozen",
+        Error::NotRevealed => "NotRevealed",
+        Error::AlreadyRevealed => "AlreadyRevealed",
+        Error::InvalidProvenance => "InvalidProvenance",
+        Error::InvalidRoyalty => "InvalidRoyalty",
+        Error::TokenLocked => "TokenLocked",
+        Error::NotPendingAdmin => "NotPendingAdmin",
+        Error::UpgradeFailed => "UpgradeFailed",
+      };
+      PSP34Error::Custom(String::from(custom))
+    }
   }
 
   /// Event emitted when a token transfer occurs.
@@ -108,18 +312,100 @@ pub mod erc721 {
     #[ink(topic)]
     to: Option<AccountId>,
     #[ink(topic)]
-    id: TokenId,
+    id: Id,
   }
 
   /// Event emitted when a token approve occurs.
+  ///
+  /// If `id` is `None`, the operator is approved or disapproved for all tokens of the owner.
   #[ink(event)]
   pub struct Approval {
     #[ink(topic)]
-    from: AccountId,
+    owner: AccountId,
+    #[ink(topic)]
+    operator: AccountId,
+    #[ink(topic)]
+    id: Option<Id>,
+    approved: bool,
+  }
+
+  /// Event emitted when a token is minted, transferred or approved to a stealth address.
+  ///
+  /// The receivers of royalties are announced too, under the id `0` for the default royalty.
+  /// `view_tag` is the first byte of the shared secret hash computed by the sender, so recipients
+  /// can discard most announcements without elliptic curve operations.
+  #[ink(event)]
+  pub struct Announcement {
+    #[ink(topic)]
+    id: Id,
+    stealth_address: AccountId,
+    ephemeral_public_key: EphemeralPublicKey,
+    view_tag: u8,
+  }
+
+  /// Event emitted when the default royalty or the royalty of a token is set.
+  ///
+  /// `id` is `None` for the default royalty of the collection, and `royalty` is `None` when the
+  /// royalty of the token is reset to the default royalty.
+  #[ink(event)]
+  pub struct RoyaltySet {
+    #[ink(topic)]
+    id: Option<Id>,
+    royalty: Option<(AccountId, u16)>,
+  }
+
+  /// Event emitted when the admin proposes a new admin, see `transfer_ownership`.
+  #[ink(event)]
+  pub struct OwnershipTransferStarted {
+    #[ink(topic)]
+    previous_admin: AccountId,
+    #[ink(topic)]
+    new_admin: AccountId,
+  }
+
+  /// Event emitted when the admin role is transferred.
+  #[ink(event)]
+  pub struct OwnershipTransferred {
     #[ink(topic)]
-    to: AccountId,
+    previous_admin: AccountId,
     #[ink(topic)]
-    id: TokenId,
+    new_admin: AccountId,
+  }
+
+  /// Event emitted when the code of the contract is upgraded.
+  #[ink(event)]
+  pub struct Upgraded {
+    code_hash: [u8; 32],
+  }
+
+  /// Event emitted when a token is locked, see ERC-5192.
+  #[ink(event)]
+  pub struct Locked {
+    #[ink(topic)]
+    id: Id,
+  }
+
+  /// Event emitted when a token is unlocked, see ERC-5192.
+  #[ink(event)]
+  pub struct Unlocked {
+    #[ink(topic)]
+    id: Id,
+  }
+
+  /// Event emitted when an attribute of a collection or token is set.
+  #[ink(event)]
+  pub struct AttributeSet {
+    #[ink(topic)]
+    id: Id,
+    key: Vec<u8>,
+    data: Vec<u8>,
+  }
+
+  /// Event emitted when the hidden metadata is revealed.
+  #[ink(event)]
+  pub struct MetadataRevealed {
+    base_uri: String,
+    provenance_hash: [u8; 32],
   }
 
   #[ink(impl)]
@@ -138,371 +424,536 @@ pub mod erc721 {
       ink_lang::utils::initialize_contract(|contract| Self::new_init(contract, base_uri))
     }
 
+    /// Returns a anonymous NFT contract instance with hidden metadata
+    ///
+    /// # Arguments
+    ///
+    /// * `placeholder_uri` - URI returned for every token until the metadata is revealed
+    /// * `provenance_hash` - Keccak-256 hash of the hidden base URI followed by a 32 bytes salt
+    ///
+    /// Creates a new ERC-721 token contract, whose base URI is revealed later by `reveal`.
+    #[ink(constructor)]
+    pub fn new_hidden(placeholder_uri: String, provenance_hash: [u8; 32]) -> Self {
+      ink_lang::utils::initialize_contract(|contract| {
+        Self::new_hidden_init(contract, placeholder_uri, provenance_hash)
+      })
+    }
+
+    /// Returns a soulbound anonymous NFT contract instance with the base_uri given
+    ///
+    /// # Arguments
+    ///
+    /// * `base_uri` - Base Uniform Resource Identifier (URI)
+    ///
+    /// Creates a new ERC-721 token contract, whose tokens cannot be transferred but can be burned.
+    #[ink(constructor)]
+    pub fn new_soulbound(base_uri: String) -> Self {
+      ink_lang::utils::initialize_contract(|contract: &mut Self| {
+        Self::new_init(contract, base_uri);
+        contract.soulbound = true;
+      })
+    }
+
     /// Default initializes the ERC-721 contract with the specified base URI.
+    ///
+    /// The caller becomes the admin of the contract.
     fn new_init(&mut self, base_uri: String) {
       self.base_uri = base_uri;
+      self.admin = self.env().caller();
+      self.revealed = true;
+      self.storage_version = STORAGE_VERSION;
     }
 
-    /// Returns the base Uniform Resource Identifier (URI)
+    /// Initializes the ERC-721 contract with hidden metadata.
     ///
-    /// Returns the base URI. This will be automatically added as a prefix in tokenURI to each token’s URI, or to the token ID if no specific URI is set for that token ID.
-    #[ink(message)]
-    pub fn base_uri(&self) -> String {
-      self.base_uri.clone()
+    /// The caller becomes the admin of the contract.
+    fn new_hidden_init(&mut self, placeholder_uri: String, provenance_hash: [u8; 32]) {
+      self.admin = self.env().caller();
+      self.placeholder_uri = placeholder_uri;
+      self.provenance_hash = Some(provenance_hash);
+      self.storage_version = STORAGE_VERSION;
     }
 
-    /// Returns the Uniform Resource Identifier (URI) for tokenId token.
+    /// Returns `true` if the token URIs are revealed.
     #[ink(message)]
-    pub fn token_uri(&self, id: TokenId) -> String {
-      self.base_uri.clone() + "/" + &id.to_string()
+    pub fn revealed(&self) -> bool {
+      self.revealed
     }
 
-    /// Returns the nonce of the token.
-    ///
-    /// Every signature signed by the token owner needs to hash the latest token nonce
+    /// Returns the provenance hash committed at construction, if the metadata was hidden.
     #[ink(message)]
-    pub fn token_nonce_of(&self, id: TokenId) -> u32 {
-      self.token_nonce.get(id).unwrap_or(0)
+    pub fn provenance_hash(&self) -> Option<[u8; 32]> {
+      self.provenance_hash
     }
 
-    /// Returns the balance of the owner.
+    /// Reveals the hidden metadata. Only the admin can reveal it.
     ///
-    /// This represents the amount of unique tokens the owner has.
+    /// The Keccak-256 hash of `base_uri` followed by `salt` must match the provenance hash.
     #[ink(message)]
-    pub fn balance_of(&self, owner: AccountId) -> u32 {
-      self.balance_of_or_zero(&owner)
+    pub fn reveal(&mut self, base_uri: String, salt: [u8; 32]) -> Result<(), Error> {
+      self.ensure_admin()?;
+      if self.revealed {
+        return Err(Error::AlreadyRevealed);
+      }
+      let provenance_hash = self.provenance_hash.ok_or(Error::InvalidProvenance)?;
+
+      let mut input = Vec::new();
+      input.extend(base_uri.as_bytes());
+      input.extend(salt);
+
+      let mut hash: [u8; 32] = [0; 32];
+      ink_env::hash_bytes::<ink_env::hash::Keccak256>(&input, &mut hash);
+      if hash != provenance_hash {
+        return Err(Error::InvalidProvenance);
+      }
+
+      self.base_uri = base_uri.clone();
+      self.revealed = true;
+      self.env().emit_event(MetadataRevealed {
+        base_uri,
+        provenance_hash,
+      });
+      Ok(())
     }
 
-    /// Returns the owner of the token.
+    /// Returns the admin of the contract.
     #[ink(message)]
-    pub fn owner_of(&self, id: TokenId) -> Option<AccountId> {
-      self.token_owner.get(id)
+    pub fn admin(&self) -> AccountId {
+      self.admin
     }
 
-    /// Returns the approved account ID for this token if any.
+    /// Returns the account proposed as new admin, if any.
     #[ink(message)]
-    pub fn get_approved(&self, id: TokenId) -> Option<AccountId> {
-      self.token_approvals.get(id)
+    pub fn pending_admin(&self) -> Option<AccountId> {
+      self.pending_admin
     }
 
-    /// Approves the account to transfer the specified token on behalf of the caller.
+    /// Proposes `new_admin` as the new admin. Only the admin can propose it.
+    ///
+    /// The admin role is transferred once the new admin calls `accept_ownership`.
+    /// Proposing another account replaces the pending one.
     #[ink(message)]
-    pub fn approve(
-      &mut self,
-      to: AccountId,
-      id: TokenId,
-      ephemeral_public_key: String,
-      signature: String,
-    ) -> Result<(), Error> {
-      self.approve_for(&to, id, ephemeral_public_key, signature)?;
+    pub fn transfer_ownership(&mut self, new_admin: AccountId) -> Result<(), Error> {
+      self.ensure_admin()?;
+      if new_admin == AccountId::from([0x0; 32]) {
+        return Err(Error::NotAllowed);
+      }
+
+      self.pending_admin = Some(new_admin);
+      self.env().emit_event(OwnershipTransferStarted {
+        previous_admin: self.admin,
+        new_admin,
+      });
       Ok(())
     }
 
-    /// Returns the ephemeral public key by NFT id.
+    /// Accepts the admin role proposed by `transfer_ownership`.
     #[ink(message)]
-    pub fn ephemeral_public_key_of(&self, id: TokenId) -> Option<String> {
-      self.token_ephemeral.get(id)
+    pub fn accept_ownership(&mut self) -> Result<(), Error> {
+      let caller = self.env().caller();
+      if self.pending_admin != Some(caller) {
+        return Err(Error::NotPendingAdmin);
+      }
+
+      let previous_admin = self.admin;
+      self.admin = caller;
+      self.pending_admin = None;
+      self.env().emit_event(OwnershipTransferred {
+        previous_admin,
+        new_admin: caller,
+      });
+      Ok(())
     }
 
-    /// Returns the toatl supply.
+    /// Returns the version of the storage layout.
     #[ink(message)]
-    pub fn total_supply(&self) -> u32 {
-      self.total_supply
+    pub fn storage_version(&self) -> u32 {
+      self.storage_version
     }
 
-    /// Transfers the token from the caller to the given `AccountId`.
+    /// Replaces the code of the contract by the code uploaded with `code_hash`. Only the admin can upgrade it.
+    ///
+    /// The new code is used from the next call on, and must keep the storage layout, see `STORAGE_VERSION`.
     #[ink(message)]
-    pub fn transfer(
-      &mut self,
-      to: AccountId,
-      id: TokenId,
-      ephemeral_public_key: String,
-      signature: String,
-    ) -> Result<(), Error> {
-      // hash input params
-      let messag_hash = self.hash_message(to, id, ephemeral_public_key.clone());
-      // recover signer
-      let signer = self.recover_signer(&messag_hash, &signature)?;
-
-      self.transfer_token_from(&signer, &to, id, ephemeral_public_key)?;
+    pub fn set_code_hash(&mut self, code_hash: [u8; 32]) -> Result<(), Error> {
+      self.ensure_admin()?;
+      Self::replace_code(&code_hash).map_err(|_| Error::UpgradeFailed)?;
 
+      self.env().emit_event(Upgraded { code_hash });
       Ok(())
     }
 
-    /// Transfer approved or owned token.
+    /// Returns `true` if the metadata has been frozen.
     #[ink(message)]
-    pub fn transfer_from(
-      &mut self,
-      from: AccountId,
-      to: AccountId,
-      id: TokenId,
-      ephemeral_public_key: String,
-      signature: String,
-    ) -> Result<(), Error> {
-      // hash input params
-      let messag_hash = self.hash_message(to, id, ephemeral_public_key.clone());
-      // recover signer
-      let signer = self.recover_signer(&messag_hash, &signature)?;
+    pub fn metadata_frozen(&self) -> bool {
+      self.metadata_frozen
+    }
 
-      if Some(signer) != self.get_approved(id) {
-        return Err(Error::NotApproved);
+    /// Sets the base URI. Only the admin can set it, until the metadata is frozen.
+    ///
+    /// Hidden metadata has to be revealed first.
+    #[ink(message)]
+    pub fn set_base_uri(&mut self, base_uri: String) -> Result<(), Error> {
+      self.ensure_metadata_editable()?;
+      if !self.revealed {
+        return Err(Error::NotRevealed);
       }
+      self.base_uri = base_uri;
+      Ok(())
+    }
+
+    /// Enables or disables the `.json` suffix of the URI derived from the base URI.
+    #[ink(message)]
+    pub fn set_uri_suffix(&mut self, enabled: bool) -> Result<(), Error> {
+      self.ensure_metadata_editable()?;
+      self.uri_suffix = enabled;
+      Ok(())
+    }
 
-      self.transfer_token_from(&from, &to, id, ephemeral_public_key)?;
+    /// Sets the URI of an existing token, which overrides the URI derived from the base URI.
+    ///
+    /// An empty URI removes the override.
+    #[ink(message)]
+    pub fn set_token_uri(&mut self, id: Id, uri: String) -> Result<(), Error> {
+      self.ensure_metadata_editable()?;
+      let id = Self::token_id(&id)?;
+      if !self.exists(id) {
+        return Err(Error::TokenNotFound);
+      }
 
+      if uri.is_empty() {
+        self.token_uris.remove(id);
+      } else {
+        self.token_uris.insert(id, &uri);
+      }
       Ok(())
     }
 
-    /// Creates a new token.
+    /// Sets the attribute `key` of the collection or an existing token to `value`.
+    ///
+    /// The attributes are returned by the PSP34 `get_attribute` query.
     #[ink(message)]
-    pub fn mint(&mut self, owner: AccountId, ephemeral_public_key: String) -> Result<(), Error> {
-      self.total_supply += 1;
-      let id = self.total_supply;
+    pub fn set_attribute(&mut self, id: Id, key: Vec<u8>, value: Vec<u8>) -> Result<(), Error> {
+      self.ensure_metadata_editable()?;
+      if id != self.collection_id() && !self.exists(Self::token_id(&id)?) {
+        return Err(Error::TokenNotFound);
+      }
 
-      self.add_token_to(&owner, id)?;
-      self.env().emit_event(Transfer {
-        from: Some(AccountId::from([0x0; 32])),
-        to: Some(owner),
+      self.attributes.insert((&id, &key), &value);
+      self.env().emit_event(AttributeSet {
         id,
+        key,
+        data: value,
       });
-      self.add_ephemeral_public_key(id, ephemeral_public_key);
-      self.token_nonce.insert(id, &1);
+      Ok(())
+    }
 
+    /// Freezes the metadata, so the base URI, token URIs and attributes can no longer be changed.
+    #[ink(message)]
+    pub fn freeze_metadata(&mut self) -> Result<(), Error> {
+      self.ensure_metadata_editable()?;
+      self.metadata_frozen = true;
       Ok(())
     }
 
-    /// Deletes an existing token. Only the owner can burn the token.
+    /// Returns `true` if the token is locked, i.e. it cannot be transferred or approved.
+    ///
+    /// Follows ERC-5192, so querying a token which does not exist fails.
     #[ink(message)]
-    pub fn burn(&mut self, id: TokenId, signature: String) -> Result<(), Error> {
-      let mut input = Vec::new();
-      input.extend(id.to_be_bytes());
-      input.extend(self.token_nonce_of(id).to_be_bytes());
+    pub fn locked(&self, id: Id) -> Result<bool, Error> {
+      let id = Self::token_id(&id)?;
+      if !self.exists(id) {
+        return Err(Error::TokenNotFound);
+      }
+      Ok(self.is_locked(id))
+    }
 
-      let mut messag_hash: [u8; 32] = [0; 32];
-      ink_env::hash_bytes::<ink_env::hash::Keccak256>(&input, &mut messag_hash);
+    /// Locks or unlocks an existing token. Only the admin can lock it.
+    ///
+    /// Tokens of a soulbound collection cannot be unlocked.
+    #[ink(message)]
+    pub fn set_locked(&mut self, id: Id, locked: bool) -> Result<(), Error> {
+      self.ensure_admin()?;
+      let id = Self::token_id(&id)?;
+      if !self.exists(id) {
+        return Err(Error::TokenNotFound);
+      }
+      if self.soulbound {
+        return Err(Error::TokenLocked);
+      }
+      if self.is_locked(id) == locked {
+        return Ok(());
+      }
 
-      let signer = self.recover_signer(&messag_hash, &signature)?;
+      if locked {
+        self.locked_tokens.insert(id, &());
+        self.env().emit_event(Locked { id: Id::U32(id) });
+      } else {
+        self.locked_tokens.remove(id);
+        self.env().emit_event(Unlocked { id: Id::U32(id) });
+      }
+      Ok(())
+    }
 
-      let owner = self.token_owner.get(id).ok_or(Error::TokenNotFound)?;
-      if owner != signer {
-        return Err(Error::NotOwner);
-      };
+    /// Returns the royalty receiver and amount of token `id` sold for `sale_price`.
+    ///
+    /// The royalty set for the token by `set_token_royalty` takes precedence over the default royalty.
+    #[ink(message)]
+    pub fn royalty_info(&self, id: Id, sale_price: Balance) -> (AccountId, Balance) {
+      let (receiver, fraction) = Self::token_id(&id)
+        .ok()
+        .and_then(|id| self.token_royalties.get(id))
+        .unwrap_or(self.default_royalty);
+
+      let fraction = fraction as Balance;
+      let denominator = ROYALTY_DENOMINATOR as Balance;
+      // Split the sale price to avoid an overflow of the multiplication.
+      let amount =
+        sale_price / denominator * fraction + sale_price % denominator * fraction / denominator;
+
+      (receiver, amount)
+    }
 
-      let count = self
-        .owned_tokens_count
-        .get(signer)
-        .map(|c| c - 1)
-        .ok_or(Error::CannotFetchValue)?;
-      self.owned_tokens_count.insert(signer, &count);
-      self.token_owner.remove(id);
-      self.token_nonce.remove(id);
-      self.total_supply -= 1;
+    /// Sets the default royalty of the collection. Only the admin can set it.
+    ///
+    /// The receiver can be a stealth address, which keeps the royalty income unlinkable.
+    /// If its ephemeral public key is given, it is announced with the view tag like the ones of
+    /// minted tokens, under the id `0`, so the creator can find the stealth address and derive its
+    /// private key. Other receivers are not announced.
+    /// The fraction is given in basis points of the sale price.
+    #[ink(message)]
+    pub fn set_default_royalty(
+      &mut self,
+      receiver: AccountId,
+      fraction: u16,
+      ephemeral_public_key: Option<String>,
+      view_tag: u8,
+    ) -> Result<(), Error> {
+      self.ensure_admin()?;
+      if fraction > ROYALTY_DENOMINATOR {
+        return Err(Error::InvalidRoyalty);
+      }
+      let ephemeral_public_key = ephemeral_public_key
+        .map(|key| self.decode_ephemeral_public_key(&key))
+        .transpose()?;
+
+      self.default_royalty = (receiver, fraction);
+      self.env().emit_event(RoyaltySet {
+        id: None,
+        royalty: Some((receiver, fraction)),
+      });
+      if let Some(ephemeral_public_key) = ephemeral_public_key {
+        self.announce(DEFAULT_ROYALTY_ID, receiver, ephemeral_public_key, view_tag);
+      }
+      Ok(())
+    }
 
-      self.env().emit_event(Transfer {
-        from: Some(signer),
-        to: Some(AccountId::from([0x0; 32])),
-        id,
+    /// Sets the royalty of an existing token, which overrides the default royalty.
+    /// Only the admin can set it.
+    ///
+    /// The ephemeral public key of a stealth receiver is announced under the token id,
+    /// see `set_default_royalty`.
+    #[ink(message)]
+    pub fn set_token_royalty(
+      &mut self,
+      id: Id,
+      receiver: AccountId,
+      fraction: u16,
+      ephemeral_public_key: Option<String>,
+      view_tag: u8,
+    ) -> Result<(), Error> {
+      self.ensure_admin()?;
+      if fraction > ROYALTY_DENOMINATOR {
+        return Err(Error::InvalidRoyalty);
+      }
+      let id = Self::token_id(&id)?;
+      if !self.exists(id) {
+        return Err(Error::TokenNotFound);
+      }
+      let ephemeral_public_key = ephemeral_public_key
+        .map(|key| self.decode_ephemeral_public_key(&key))
+        .transpose()?;
+
+      self.token_royalties.insert(id, &(receiver, fraction));
+      self.env().emit_event(RoyaltySet {
+        id: Some(Id::U32(id)),
+        royalty: Some((receiver, fraction)),
       });
+      if let Some(ephemeral_public_key) = ephemeral_public_key {
+        self.announce(id, receiver, ephemeral_public_key, view_tag);
+      }
+      Ok(())
+    }
 
+    /// Removes the royalty of the token, so the default royalty applies again.
+    /// Only the admin can reset it.
+    #[ink(message)]
+    pub fn reset_token_royalty(&mut self, id: Id) -> Result<(), Error> {
+      self.ensure_admin()?;
+      let id = Self::token_id(&id)?;
+      if !self.exists(id) {
+        return Err(Error::TokenNotFound);
+      }
+      self.token_royalties.remove(id);
+      self.env().emit_event(RoyaltySet {
+        id: Some(Id::U32(id)),
+        royalty: None,
+      });
       Ok(())
     }
 
-    /// Hash receiver + NFT id + ephemeral_public_key
-    /// return the hashed value
-    fn hash_message(&self, to: AccountId, id: TokenId, ephemeral_public_key: String) -> [u8; 32] {
-      let mut input = Vec::new();
+    /// Returns the base Uniform Resource Identifier (URI)
+    ///
+    /// Returns the base URI. This will be automatically added as a prefix in tokenURI to each token’s URI, or to the token ID if no specific URI is set for that token ID.
+    #[ink(message)]
+    pub fn base_uri(&self) -> String {
+      self.base_uri.clone()
+    }
 
-      // raw message data compose of to + ephemeral_public_key + id
-      let to_bytes: [u8; 32] = *to.as_ref();
-      let ephemeral_public_key_bytes: [u8; 33] = self
-        .hex_decode(&ephemeral_public_key)
-        .unwrap()
-        .as_slice()
-        .try_into()
-        .unwrap();
-      input.extend(to_bytes.iter());
-      input.extend(ephemeral_public_key_bytes.iter());
-      input.extend(id.to_be_bytes());
-      input.extend(self.token_nonce_of(id).to_be_bytes());
-
-      // use keccka256 to hash the raw message data
-      let mut messag_hash: [u8; 32] = [0; 32];
-      ink_env::hash_bytes::<ink_env::hash::Keccak256>(&input, &mut messag_hash);
-
-      messag_hash
-    }
+    /// Returns the Uniform Resource Identifier (URI) for tokenId token.
+    ///
+    /// The URI set by `set_token_uri` takes precedence over the one derived from the base URI.
+    /// Until hidden metadata is revealed, the placeholder URI is returned instead.
+    #[ink(message)]
+    pub fn token_uri(&self, id: Id) -> Result<String, Error> {
+      let id = Self::token_id(&id)?;
+      if !self.exists(id) {
+        return Err(Error::TokenNotFound);
+      }
 
-    /// Recovers the AccountId for given signature and message_hash,
-    /// and return the signer
-    fn recover_signer(
-      &self,
-      message_hash: &[u8; 32],
-      signature: &String,
-    ) -> Result<AccountId, Error> {
-      // hex string to bytes
-      let signature: [u8; 65] = self
-        .hex_decode(signature)
-        .unwrap()
-        .as_slice()
-        .try_into()
-        .unwrap();
-      // recover the compressed ECDSA public key from signature and message_hash
-      let mut recovered_public_key = [0u8; 33];
-      ink_env::ecdsa_recover(&signature, message_hash, &mut recovered_public_key).unwrap();
+      if !self.revealed {
+        return Ok(self.placeholder_uri.clone());
+      }
 
-      // encode the compressed ECDSA public key to AccountId
-      let mut public_key_hash = [0u8; 32];
-      ink_env::hash_bytes::<ink_env::hash::Blake2x256>(&recovered_public_key, &mut public_key_hash);
-      let signer = AccountId::from(public_key_hash);
+      if let Some(uri) = self.token_uris.get(id) {
+        return Ok(uri);
+      }
 
-      Ok(signer)
+      let uri = self.base_uri.clone() + "/" + &id.to_string();
+      if self.uri_suffix {
+        Ok(uri + ".json")
+      } else {
+        Ok(uri)
+      }
     }
 
-    /// Decodes a hex string into raw bytes.
+    /// Returns the nonce of the token.
     ///
-    /// Both, upper and lower case characters are valid in the input string and can
-    /// even be mixed (e.g. `f9b4ca`, `F9B4CA` and `f9B4Ca` are all valid strings).
-    fn hex_decode(&self, hex: &String) -> Result<Vec<u8>, Error> {
-      let hex: &[u8] = hex.as_ref();
-      if hex.len() % 2 != 0 {
-        return Err(Error::OddLength);
-      }
-      hex
-        .chunks(2)
-        .enumerate()
-        .map(|(_, pair)| Ok(self.val(pair[0])? << 4 | self.val(pair[1])?))
-        .collect()
+    /// Every signature signed by the token owner needs to hash the latest token nonce
+    #[ink(message)]
+    pub fn token_nonce_of(&self, id: Id) -> u32 {
+      Self::token_id(&id).map(|id| self.nonce_of(id)).unwrap_or(0)
     }
 
-    fn val(&self, c: u8) -> Result<u8, Error> {
-      match c {
-        b'A'..=b'F' => Ok(c - b'A' + 10),
-        b'a'..=b'f' => Ok(c - b'a' + 10),
-        b'0'..=b'9' => Ok(c - b'0'),
-        _ => Err(Error::InvalidHexCharacter),
-      }
+    /// Returns the approved account ID for this token if any.
+    #[ink(message)]
+    pub fn get_approved(&self, id: Id) -> Option<AccountId> {
+      Self::token_id(&id)
+        .ok()
+        .and_then(|id| self.token_approvals.get(id))
     }
 
-    /// Transfers token `id` `from` the sender to the `to` `AccountId`.
-    fn transfer_token_from(
+    /// Approves the account to transfer the specified token on behalf of the signer.
+    ///
+    /// An existing approval of the token is replaced by the new one.
+    #[ink(message)]
+    pub fn stealth_approve(
       &mut self,
-      from: &AccountId,
-      to: &AccountId,
-      id: TokenId,
+      to: AccountId,
+      id: Id,
       ephemeral_public_key: String,
+      view_tag: u8,
+      signature: String,
     ) -> Result<(), Error> {
-      if !self.exists(id) {
-        return Err(Error::TokenNotFound);
-      };
-      if !self.approved_or_owner(Some(*from), id) {
-        return Err(Error::NotApproved);
-      };
-
-      self.clear_approval(id);
-      self.remove_token_from(from, id)?;
-      self.add_token_to(to, id)?;
-      self.add_ephemeral_public_key(id, ephemeral_public_key);
-
-      // Update token nonce
-      let nonce = self
-        .token_nonce
-        .get(id)
-        .map(|c| c + 1)
-        .ok_or(Error::CannotFetchValue)?;
-      self.token_nonce.insert(id, &nonce);
-
-      self.env().emit_event(Transfer {
-        from: Some(*from),
-        to: Some(*to),
-        id,
-      });
+      let id = Self::token_id(&id)?;
+      let ephemeral_public_key = self.decode_ephemeral_public_key(&ephemeral_public_key)?;
+      self.approve_for(&to, id, ephemeral_public_key, signature)?;
+      self.announce(id, to, ephemeral_public_key, view_tag);
       Ok(())
     }
 
-    /// Removes token `id` from the owner.
-    fn remove_token_from(&mut self, from: &AccountId, id: TokenId) -> Result<(), Error> {
-      let Self {
-        token_owner,
-        owned_tokens_count,
-        ..
-      } = self;
+    /// Returns the nonce of the owner's operator approvals.
+    ///
+    /// Every operator approval signed by the owner needs to hash the latest owner nonce
+    #[ink(message)]
+    pub fn operator_nonce_of(&self, owner: AccountId) -> u32 {
+      self.operator_nonce.get(owner).unwrap_or(0)
+    }
 
-      if !token_owner.contains(id) {
-        return Err(Error::TokenNotFound);
-      }
+    /// Returns `true` if the operator is approved by the owner.
+    #[ink(message)]
+    pub fn is_approved_for_all(&self, owner: AccountId, operator: AccountId) -> bool {
+      self.approved_for_all(owner, operator)
+    }
 
-      let count = owned_tokens_count
-        .get(from)
-        .map(|c| c - 1)
-        .ok_or(Error::CannotFetchValue)?;
-      owned_tokens_count.insert(from, &count);
-      token_owner.remove(id);
+    /// Approves or disapproves the operator for all tokens of the owner.
+    ///
+    /// The signature is signed by the owner over operator + approved + owner nonce.
+    #[ink(message)]
+    pub fn set_approval_for_all(
+      &mut self,
+      owner: AccountId,
+      operator: AccountId,
+      approved: bool,
+      signature: String,
+    ) -> Result<(), Error> {
+      // hash operator + approved + owner nonce
+      let mut input = Vec::new();
+      let operator_bytes: [u8; 32] = *operator.as_ref();
+      input.extend(operator_bytes.iter());
+      input.push(approved as u8);
+      input.extend(self.operator_nonce_of(owner).to_be_bytes());
 
-      Ok(())
-    }
+      let mut messag_hash: [u8; 32] = [0; 32];
+      ink_env::hash_bytes::<ink_env::hash::Keccak256>(&input, &mut messag_hash);
 
-    /// Adds the token `id` to the `to` AccountID.
-    fn add_token_to(&mut self, to: &AccountId, id: TokenId) -> Result<(), Error> {
-      let Self {
-        token_owner,
-        owned_tokens_count,
-        ..
-      } = self;
+      let signer = self.recover_signer(&messag_hash, &signature)?;
+      if signer != owner {
+        return Err(Error::NotOwner);
+      }
 
-      if *to == AccountId::from([0x0; 32]) {
+      if operator == owner || operator == AccountId::from([0x0; 32]) {
         return Err(Error::NotAllowed);
-      };
+      }
 
-      let count = owned_tokens_count.get(to).map(|c| c + 1).unwrap_or(1);
+      self.approve_operator(owner, operator, approved);
 
-      owned_tokens_count.insert(to, &count);
-      token_owner.insert(id, to);
+      // Update owner nonce
+      let nonce = self.operator_nonce_of(owner) + 1;
+      self.operator_nonce.insert(owner, &nonce);
 
       Ok(())
     }
 
-    /// Adds ephemeral public key to TokenId
-    fn add_ephemeral_public_key(&mut self, id: TokenId, ephemeral_public_key: String) {
-      if self.token_ephemeral.contains(id) {
-        self.token_ephemeral.remove(id);
-      }
-
-      self.token_ephemeral.insert(id, &ephemeral_public_key);
-    }
-
-    /// Approve the passed `AccountId` to transfer the specified token on behalf of the message's sender.
-    fn approve_for(
-      &mut self,
-      to: &AccountId,
-      id: TokenId,
-      ephemeral_public_key: String,
-      signature: String,
-    ) -> Result<(), Error> {
+    /// Revokes the existing approval of the token.
+    ///
+    /// The signature is signed by the token owner over the zero address + NFT id + nonce.
+    #[ink(message)]
+    pub fn revoke_approval(&mut self, id: Id, signature: String) -> Result<(), Error> {
+      let id = Self::token_id(&id)?;
       if !self.exists(id) {
         return Err(Error::TokenNotFound);
       };
 
-      // hash input params
-      let messag_hash = self.hash_message(*to, id, ephemeral_public_key.clone());
-      // recover signer
-      let signer = self.recover_signer(&messag_hash, &signature)?;
+      // hash the zero address + NFT id + nonce, i.e. an approval to nobody
+      let mut input = Vec::new();
+      input.extend([0u8; 32]);
+      input.extend(id.to_be_bytes());
+      input.extend(self.nonce_of(id).to_be_bytes());
 
-      let owner = self.owner_of(id);
-      if owner != Some(signer) {
-        return Err(Error::NotAllowed);
-      };
+      let mut messag_hash: [u8; 32] = [0; 32];
+      ink_env::hash_bytes::<ink_env::hash::Keccak256>(&input, &mut messag_hash);
 
-      if *to == AccountId::from([0x0; 32]) {
+      let signer = self.recover_signer(&messag_hash, &signature)?;
+
+      let owner = self.token_owner.get(id).ok_or(Error::TokenNotFound)?;
+      if owner != signer && !self.approved_for_all(owner, signer) {
         return Err(Error::NotAllowed);
       };
 
-      if self.token_approvals.contains(id) {
-        return Err(Error::CannotInsert);
-      } else {
-        self.token_approvals.insert(id, to);
-      }
+      let operator = self.token_approvals.get(id).ok_or(Error::NotApproved)?;
 
-      self.add_ephemeral_public_key(id, ephemeral_public_key);
+      self.clear_approval(id);
 
       // Update token nonce
       let nonce = self
@@ -513,279 +964,2841 @@ pub mod erc721 {
       self.token_nonce.insert(id, &nonce);
 
       self.env().emit_event(Approval {
-        from: signer,
-        to: *to,
-        id,
+        owner,
+        operator,
+        id: Some(Id::U32(id)),
+        approved: false,
       });
 
       Ok(())
     }
 
-    /// Removes existing approval from token `id`.
-    fn clear_approval(&mut self, id: TokenId) {
-      self.token_approvals.remove(id);
+    /// Returns the ephemeral public key by NFT id.
+    #[ink(message)]
+    pub fn ephemeral_public_key_of(&self, id: Id) -> Option<EphemeralPublicKey> {
+      Self::token_id(&id)
+        .ok()
+        .and_then(|id| self.token_ephemeral.get(id))
     }
 
-    // Returns the total number of tokens from an account.
-    fn balance_of_or_zero(&self, id: &AccountId) -> u32 {
-      self.owned_tokens_count.get(id).unwrap_or(0)
+    /// Returns the token ID at `index` of all existing tokens.
+    ///
+    /// The order of the tokens changes when a token is burned.
+    #[ink(message)]
+    pub fn token_by_index(&self, index: u32) -> Option<TokenId> {
+      self.all_tokens.get(index)
     }
 
-    /// Returns true if the `AccountId` `from` is the owner of token `id`
-    /// or it has been approved on behalf of the token `id` owner.
-    fn approved_or_owner(&self, from: Option<AccountId>, id: TokenId) -> bool {
-      let owner = self.owner_of(id);
-      from != Some(AccountId::from([0x0; 32]))
-        && (from == owner || from == self.token_approvals.get(id))
+    /// Returns up to `count` existing tokens starting at `index`, with their owner,
+    /// ephemeral public key and nonce.
+    ///
+    /// At most 100 tokens are returned per call, so wallets scanning for their stealth addresses
+    /// can sync the collection page by page.
+    #[ink(message)]
+    pub fn tokens(
+      &self,
+      start: u32,
+      count: u32,
+    ) -> Vec<(TokenId, AccountId, Option<EphemeralPublicKey>, u32)> {
+      let end = start
+        .saturating_add(count.min(MAX_ITEMS_PER_QUERY))
+        .min(self.total_supply);
+
+      (start..end)
+        .filter_map(|index| self.all_tokens.get(index))
+        .filter_map(|id| {
+          self
+            .token_owner
+            .get(id)
+            .map(|owner| (id, owner, self.token_ephemeral.get(id), self.nonce_of(id)))
+        })
+        .collect()
     }
 
-    /// Returns true if token `id` exists or false if it does not.
-    fn exists(&self, id: TokenId) -> bool {
-      self.token_owner.contains(id)
+    /// Returns the owners of the given tokens, `None` for tokens which do not exist.
+    #[ink(message)]
+    pub fn owners_of(&self, ids: Vec<TokenId>) -> Vec<Option<AccountId>> {
+      ids.into_iter().map(|id| self.token_owner.get(id)).collect()
     }
-  }
 
-  /// Unit tests
-  #[cfg(test)]
-  mod tests {
-    // Imports all the definitions from the outer scope so we can use them here.
-    use super::*;
-    use ink_lang as ink;
-    // Because the test environment does not support elliptic curve APIs, public keys and signatures have to be hard-coded for test purposes.
-    // Alice ephemeral public key.
-    const ALICE_EPHEMERAL_PUBLIC_KEY: &str =
-      "023283ba9bfc9f689cb4ca88d14734aea6e3bdded740d0e560e9344ab4fe825733";
-    const ALICE_ENCRYPTED_ADDRESS_BYTES: [u8; 32] = [
-      16, 106, 116, 36, 96, 18, 235, 9, 152, 150, 66, 41, 227, 178, 97, 58, 215, 25, 214, 129, 221,
-      230, 182, 93, 103, 15, 31, 51, 86, 33, 248, 192,
-    ];
+    /// Returns up to `count` ownership history entries of token `id` starting at `start`, oldest first.
+    ///
+    /// The history keeps the ephemeral public keys of previous owners, so they can rederive their
+    /// stealth addresses and prove former ownership. It is kept after the token is burned.
+    #[ink(message)]
+    pub fn token_history(&self, id: Id, start: u32, count: u32) -> Vec<HistoryEntry> {
+      let id = match Self::token_id(&id) {
+        Ok(id) => id,
+        Err(_) => return Vec::new(),
+      };
+      let end = start
+        .saturating_add(count.min(MAX_ITEMS_PER_QUERY))
+        .min(self.token_history_len.get(id).unwrap_or(0));
 
-    // Bob ephemeral public key.
-    const BOB_EPHEMERAL_PUBLIC_KEY: &str =
-      "02c5e1752c5f2d858207407c8d1e0b35a3f078a5d80dd3f109bf9ff0bfebd1f449";
-    const BOB_ENCRYPTED_ADDRESS_BYTES: [u8; 32] = [
-      163, 107, 29, 40, 120, 48, 34, 216, 17, 150, 100, 98, 245, 18, 117, 135, 100, 170, 65, 66,
-      168, 150, 165, 125, 46, 117, 207, 171, 186, 216, 207, 200,
-    ];
+      (start..end)
+        .filter_map(|index| self.token_history.get((id, index)))
+        .collect()
+    }
+
+    /// Transfers the token from the signer to the given `AccountId`.
+    #[ink(message)]
+    pub fn stealth_transfer(
+      &mut self,
+      to: AccountId,
+      id: Id,
+      ephemeral_public_key: String,
+      view_tag: u8,
+      signature: String,
+    ) -> Result<(), Error> {
+      self.signed_transfer(to, id, ephemeral_public_key, view_tag, signature, None)
+    }
+
+    /// Transfers the token from the signer to the given `AccountId`.
+    ///
+    /// If `to` is a contract, it must accept the token by its `PSP34Receiver::before_received` message.
+    #[ink(message)]
+    pub fn safe_stealth_transfer(
+      &mut self,
+      to: AccountId,
+      id: Id,
+      ephemeral_public_key: String,
+      view_tag: u8,
+      signature: String,
+      data: Vec<u8>,
+    ) -> Result<(), Error> {
+      self.signed_transfer(
+        to,
+        id,
+        ephemeral_public_key,
+        view_tag,
+        signature,
+        Some(data),
+      )
+    }
+
+    /// Transfer approved or owned token.
+    #[ink(message)]
+    pub fn transfer_from(
+      &mut self,
+      from: AccountId,
+      to: AccountId,
+      id: Id,
+      ephemeral_public_key: String,
+      view_tag: u8,
+      signature: String,
+    ) -> Result<(), Error> {
+      self.signed_transfer_from(
+        from,
+        to,
+        id,
+        ephemeral_public_key,
+        view_tag,
+        signature,
+        None,
+      )
+    }
+
+    /// Transfer approved or owned token.
+    ///
+    /// If `to` is a contract, it must accept the token by its `PSP34Receiver::before_received` message.
+    #[ink(message)]
+    pub fn safe_transfer_from(
+      &mut self,
+      from: AccountId,
+      to: AccountId,
+      id: Id,
+      ephemeral_public_key: String,
+      view_tag: u8,
+      signature: String,
+      data: Vec<u8>,
+    ) -> Result<(), Error> {
+      self.signed_transfer_from(
+        from,
+        to,
+        id,
+        ephemeral_public_key,
+        view_tag,
+        signature,
+        Some(data),
+      )
+    }
+
+    /// Transfers the token from the caller to the given `AccountId`, like the PSP34 `transfer`.
+    ///
+    /// If `to` is a contract, it must accept the token by its `PSP34Receiver::before_received` message.
+    #[ink(message)]
+    pub fn safe_transfer(&mut self, to: AccountId, id: Id, data: Vec<u8>) -> Result<(), Error> {
+      let id = Self::token_id(&id)?;
+      let caller = self.env().caller();
+      let owner = self.token_owner.get(id).ok_or(Error::TokenNotFound)?;
+      if !self.approved_or_owner(Some(caller), id) {
+        return Err(Error::NotApproved);
+      }
+
+      self.transfer_token_from(&owner, &to, id, None, Some(data))
+    }
+
+    /// Transfers a batch of tokens, each from its signer to the given `AccountId`.
+    ///
+    /// Every transfer is checked before any token is moved, so either all tokens are transferred or none.
+    #[ink(message)]
+    pub fn transfer_batch(
+      &mut self,
+      transfers: Vec<(AccountId, Id, String, u8, String)>,
+    ) -> Result<(), Error> {
+      let mut checked: Vec<(AccountId, AccountId, TokenId, EphemeralPublicKey, u8)> = Vec::new();
+      for (to, id, ephemeral_public_key, view_tag, signature) in transfers {
+        let id = Self::token_id(&id)?;
+        if checked
+          .iter()
+          .any(|(_, _, checked_id, _, _)| *checked_id == id)
+        {
+          return Err(Error::NotAllowed);
+        }
+        if to == AccountId::from([0x0; 32]) {
+          return Err(Error::NotAllowed);
+        }
+
+        let ephemeral_public_key = self.decode_ephemeral_public_key(&ephemeral_public_key)?;
+        // hash input params
+        let messag_hash = self.hash_message(to, id, &ephemeral_public_key);
+        // recover signer
+        let signer = self.recover_signer(&messag_hash, &signature)?;
+
+        let owner = self.token_owner.get(id).ok_or(Error::TokenNotFound)?;
+        if !self.approved_or_owner(Some(signer), id) {
+          return Err(Error::NotApproved);
+        }
+        if self.is_locked(id) {
+          return Err(Error::TokenLocked);
+        }
+
+        checked.push((owner, to, id, ephemeral_public_key, view_tag));
+      }
+
+      for (from, to, id, ephemeral_public_key, view_tag) in checked {
+        self.transfer_token_from(&from, &to, id, Some(ephemeral_public_key), None)?;
+        self.announce(id, to, ephemeral_public_key, view_tag);
+      }
+
+      Ok(())
+    }
+
+    /// Creates a new token.
+    #[ink(message)]
+    pub fn mint(
+      &mut self,
+      owner: AccountId,
+      ephemeral_public_key: String,
+      view_tag: u8,
+    ) -> Result<(), Error> {
+      let ephemeral_public_key = self.decode_ephemeral_public_key(&ephemeral_public_key)?;
+
+      let id = self.last_token_id + 1;
+      self.add_token_to(&owner, id)?;
+      self.last_token_id = id;
+      self.all_tokens.insert(self.total_supply, &id);
+      self.all_tokens_index.insert(id, &self.total_supply);
+      self.total_supply += 1;
+
+      self.env().emit_event(Transfer {
+        from: None,
+        to: Some(owner),
+        id: Id::U32(id),
+      });
+      self.add_ephemeral_public_key(id, ephemeral_public_key);
+      self.token_nonce.insert(id, &1);
+      self.record_history(id, owner, Some(ephemeral_public_key));
+      if self.soulbound {
+        self.env().emit_event(Locked { id: Id::U32(id) });
+      }
+      self.announce(id, owner, ephemeral_public_key, view_tag);
+
+      Ok(())
+    }
+
+    /// Deletes an existing token. Only the owner can burn the token.
+    #[ink(message)]
+    pub fn burn(&mut self, id: Id, signature: String) -> Result<(), Error> {
+      let id = Self::token_id(&id)?;
+      let mut input = Vec::new();
+      input.extend(id.to_be_bytes());
+      input.extend(self.nonce_of(id).to_be_bytes());
+
+      let mut messag_hash: [u8; 32] = [0; 32];
+      ink_env::hash_bytes::<ink_env::hash::Keccak256>(&input, &mut messag_hash);
+
+      let signer = self.recover_signer(&messag_hash, &signature)?;
+
+      let owner = self.token_owner.get(id).ok_or(Error::TokenNotFound)?;
+      if owner != signer {
+        return Err(Error::NotOwner);
+      };
+
+      let count = self
+        .owned_tokens_count
+        .get(signer)
+        .map(|c| c - 1)
+        .ok_or(Error::CannotFetchValue)?;
+      self.owned_tokens_count.insert(signer, &count);
+      self.token_owner.remove(id);
+      self.token_nonce.remove(id);
+      self.clear_approval(id);
+      self.token_ephemeral.remove(id);
+      self.token_uris.remove(id);
+      self.token_royalties.remove(id);
+      self.locked_tokens.remove(id);
+      self.remove_token_from_all_tokens(id);
+
+      self.env().emit_event(Transfer {
+        from: Some(signer),
+        to: None,
+        id: Id::U32(id),
+      });
+
+      Ok(())
+    }
+
+    /// Returns the internal token ID of the PSP34 `Id`.
+    ///
+    /// Tokens are minted with `Id::U32` ids, so other variants are never found.
+    fn token_id(id: &Id) -> Result<TokenId, Error> {
+      match id {
+        Id::U32(id) => Ok(*id),
+        _ => Err(Error::TokenNotFound),
+      }
+    }
+
+    /// Removes token `id` from the enumeration by moving the last token into its index.
+    fn remove_token_from_all_tokens(&mut self, id: TokenId) {
+      let last_index = self.total_supply - 1;
+      let index = self.all_tokens_index.get(id).unwrap_or(last_index);
+      if index != last_index {
+        if let Some(last_id) = self.all_tokens.get(last_index) {
+          self.all_tokens.insert(index, &last_id);
+          self.all_tokens_index.insert(last_id, &index);
+        }
+      }
+
+      self.all_tokens.remove(last_index);
+      self.all_tokens_index.remove(id);
+      self.total_supply = last_index;
+    }
+
+    /// Returns an error if the caller is not the admin.
+    fn ensure_admin(&self) -> Result<(), Error> {
+      if self.env().caller() != self.admin {
+        return Err(Error::NotAdmin);
+      }
+      Ok(())
+    }
+
+    /// Returns an error if the caller is not the admin, or the metadata has been frozen.
+    fn ensure_metadata_editable(&self) -> Result<(), Error> {
+      self.ensure_admin()?;
+      if self.metadata_frozen {
+        return Err(Error::MetadataFrozen);
+      }
+      Ok(())
+    }
+
+    /// Returns the nonce of the internal token ID.
+    fn nonce_of(&self, id: TokenId) -> u32 {
+      self.token_nonce.get(id).unwrap_or(0)
+    }
+
+    /// Hash receiver + NFT id + ephemeral_public_key
+    /// return the hashed value
+    fn hash_message(
+      &self,
+      to: AccountId,
+      id: TokenId,
+      ephemeral_public_key: &EphemeralPublicKey,
+    ) -> [u8; 32] {
+      let mut input = Vec::new();
+
+      // raw message data compose of to + ephemeral_public_key + id
+      let to_bytes: [u8; 32] = *to.as_ref();
+      input.extend(to_bytes.iter());
+      input.extend(ephemeral_public_key.iter());
+      input.extend(id.to_be_bytes());
+      input.extend(self.nonce_of(id).to_be_bytes());
+
+      // use keccka256 to hash the raw message data
+      let mut messag_hash: [u8; 32] = [0; 32];
+      ink_env::hash_bytes::<ink_env::hash::Keccak256>(&input, &mut messag_hash);
+
+      messag_hash
+    }
+
+    /// Recovers the AccountId for given signature and message_hash,
+    /// and return the signer, or an error if the signature is malformed
+    fn recover_signer(
+      &self,
+      message_hash: &[u8; 32],
+      signature: &String,
+    ) -> Result<AccountId, Error> {
+      // hex string to bytes
+      let signature: [u8; 65] = self
+        .hex_decode(signature)?
+        .as_slice()
+        .try_into()
+        .map_err(|_| Error::InvalidSignature)?;
+      // recover the compressed ECDSA public key from signature and message_hash
+      let mut recovered_public_key = [0u8; 33];
+      ink_env::ecdsa_recover(&signature, message_hash, &mut recovered_public_key)
+        .map_err(|_| Error::InvalidSignature)?;
+
+      // encode the compressed ECDSA public key to AccountId
+      let mut public_key_hash = [0u8; 32];
+      ink_env::hash_bytes::<ink_env::hash::Blake2x256>(&recovered_public_key, &mut public_key_hash);
+      let signer = AccountId::from(public_key_hash);
+
+      Ok(signer)
+    }
+
+    /// Decodes a hex string into a compressed ephemeral public key.
+    ///
+    /// The key must be 33 bytes long and start with `0x02` or `0x03`.
+    fn decode_ephemeral_public_key(&self, hex: &String) -> Result<EphemeralPublicKey, Error> {
+      let ephemeral_public_key: EphemeralPublicKey = self
+        .hex_decode(hex)?
+        .as_slice()
+        .try_into()
+        .map_err(|_| Error::InvalidEphemeralKey)?;
+      if ephemeral_public_key[0] != 0x02 && ephemeral_public_key[0] != 0x03 {
+        return Err(Error::InvalidEphemeralKey);
+      }
+
+      Ok(ephemeral_public_key)
+    }
+
+    /// Decodes a hex string into raw bytes.
+    ///
+    /// Both, upper and lower case characters are valid in the input string and can
+    /// even be mixed (e.g. `f9b4ca`, `F9B4CA` and `f9B4Ca` are all valid strings).
+    fn hex_decode(&self, hex: &String) -> Result<Vec<u8>, Error> {
+      let hex: &[u8] = hex.as_ref();
+      if hex.len() % 2 != 0 {
+        return Err(Error::OddLength);
+      }
+      hex
+        .chunks(2)
+        .enumerate()
+        .map(|(_, pair)| Ok(self.val(pair[0])? << 4 | self.val(pair[1])?))
+        .collect()
+    }
+
+    fn val(&self, c: u8) -> Result<u8, Error> {
+      match c {
+        b'A'..=b'F' => Ok(c - b'A' + 10),
+        b'a'..=b'f' => Ok(c - b'a' + 10),
+        b'0'..=b'9' => Ok(c - b'0'),
+        _ => Err(Error::InvalidHexCharacter),
+      }
+    }
+
+    /// Transfers the token from the signer, see `stealth_transfer`.
+    fn signed_transfer(
+      &mut self,
+      to: AccountId,
+      id: Id,
+      ephemeral_public_key: String,
+      view_tag: u8,
+      signature: String,
+      data: Option<Vec<u8>>,
+    ) -> Result<(), Error> {
+      let id = Self::token_id(&id)?;
+      let ephemeral_public_key = self.decode_ephemeral_public_key(&ephemeral_public_key)?;
+      // hash input params
+      let messag_hash = self.hash_message(to, id, &ephemeral_public_key);
+      // recover signer
+      let signer = self.recover_signer(&messag_hash, &signature)?;
+
+      self.transfer_token_from(&signer, &to, id, Some(ephemeral_public_key), data)?;
+      self.announce(id, to, ephemeral_public_key, view_tag);
+
+      Ok(())
+    }
+
+    /// Transfers the token signed by an approved account, see `transfer_from`.
+    fn signed_transfer_from(
+      &mut self,
+      from: AccountId,
+      to: AccountId,
+      id: Id,
+      ephemeral_public_key: String,
+      view_tag: u8,
+      signature: String,
+      data: Option<Vec<u8>>,
+    ) -> Result<(), Error> {
+      let id = Self::token_id(&id)?;
+      let ephemeral_public_key = self.decode_ephemeral_public_key(&ephemeral_public_key)?;
+      // hash input params
+      let messag_hash = self.hash_message(to, id, &ephemeral_public_key);
+      // recover signer
+      let signer = self.recover_signer(&messag_hash, &signature)?;
+
+      let owner = self.token_owner.get(id).ok_or(Error::TokenNotFound)?;
+      if Some(signer) != self.token_approvals.get(id) && !self.approved_for_all(owner, signer) {
+        return Err(Error::NotApproved);
+      }
+
+      self.transfer_token_from(&from, &to, id, Some(ephemeral_public_key), data)?;
+      self.announce(id, to, ephemeral_public_key, view_tag);
+
+      Ok(())
+    }
+
+    /// Transfers token `id` `from` the sender to the `to` `AccountId`.
+    ///
+    /// The ephemeral public key is removed if `to` is not a stealth address.
+    /// If `data` is given and `to` is a contract, the contract must accept the token before it is moved.
+    fn transfer_token_from(
+      &mut self,
+      from: &AccountId,
+      to: &AccountId,
+      id: TokenId,
+      ephemeral_public_key: Option<EphemeralPublicKey>,
+      data: Option<Vec<u8>>,
+    ) -> Result<(), Error> {
+      if !self.exists(id) {
+        return Err(Error::TokenNotFound);
+      };
+      if !self.approved_or_owner(Some(*from), id) {
+        return Err(Error::NotApproved);
+      };
+      if self.is_locked(id) {
+        return Err(Error::TokenLocked);
+      };
+      if *to == AccountId::from([0x0; 32]) {
+        return Err(Error::NotAllowed);
+      };
+      if let Some(data) = data {
+        self.check_receiver(from, to, id, data)?;
+      }
+
+      self.clear_approval(id);
+      self.remove_token_from(from, id)?;
+      self.add_token_to(to, id)?;
+      match ephemeral_public_key {
+        Some(ephemeral_public_key) => self.add_ephemeral_public_key(id, ephemeral_public_key),
+        None => self.token_ephemeral.remove(id),
+      }
+
+      // Update token nonce
+      let nonce = self
+        .token_nonce
+        .get(id)
+        .map(|c| c + 1)
+        .ok_or(Error::CannotFetchValue)?;
+      self.token_nonce.insert(id, &nonce);
+      self.record_history(id, *to, ephemeral_public_key);
+
+      self.env().emit_event(Transfer {
+        from: Some(*from),
+        to: Some(*to),
+        id: Id::U32(id),
+      });
+      Ok(())
+    }
+
+    /// Asks the `to` contract to accept token `id` by its `PSP34Receiver::before_received` message.
+    ///
+    /// Accounts which are not contracts, e.g. wallets and stealth addresses, always accept the token.
+    fn check_receiver(
+      &self,
+      from: &AccountId,
+      to: &AccountId,
+      id: TokenId,
+      data: Vec<u8>,
+    ) -> Result<(), Error> {
+      if !self.is_contract(to) {
+        return Ok(());
+      }
+
+      Self::check_acknowledgement(self.call_receiver(to, from, id, data))
+    }
+
+    /// Replaces the code of the contract.
+    #[cfg(not(test))]
+    fn replace_code(code_hash: &[u8; 32]) -> ink_env::Result<()> {
+      ink_env::set_code_hash(code_hash)
+    }
+
+    /// Replaces the code of the mock contract, as the off-chain environment has no code.
+    #[cfg(test)]
+    fn replace_code(code_hash: &[u8; 32]) -> ink_env::Result<()> {
+      tests::set_mock_code_hash(code_hash)
+    }
+
+    /// Returns whether the account is a contract.
+    #[cfg(not(test))]
+    fn is_contract(&self, account: &AccountId) -> bool {
+      self.env().is_contract(account)
+    }
+
+    /// Returns whether the account is a mock receiver, as the off-chain environment has no contracts.
+    #[cfg(test)]
+    fn is_contract(&self, account: &AccountId) -> bool {
+      tests::is_mock_receiver(account)
+    }
+
+    /// Calls `PSP34Receiver::before_received` of the `to` contract.
+    #[cfg(not(test))]
+    fn call_receiver(
+      &self,
+      to: &AccountId,
+      from: &AccountId,
+      id: TokenId,
+      data: Vec<u8>,
+    ) -> ink_env::Result<Result<(), PSP34ReceiverError>> {
+      ink_env::call::build_call::<Environment>()
+        .call_type(ink_env::call::Call::new().callee(*to).gas_limit(0))
+        .exec_input(
+          ink_env::call::ExecutionInput::new(ink_env::call::Selector::new(
+            crate::traits::BEFORE_RECEIVED_SELECTOR,
+          ))
+          .push_arg(self.env().caller())
+          .push_arg(*from)
+          .push_arg(Id::U32(id))
+          .push_arg(data),
+        )
+        .returns::<Result<(), PSP34ReceiverError>>()
+        .fire()
+    }
+
+    /// Calls the mock receiver `to`, as the off-chain environment cannot call contracts.
+    #[cfg(test)]
+    fn call_receiver(
+      &self,
+      to: &AccountId,
+      from: &AccountId,
+      id: TokenId,
+      data: Vec<u8>,
+    ) -> ink_env::Result<Result<(), PSP34ReceiverError>> {
+      tests::call_mock_receiver(to, self.env().caller(), *from, Id::U32(id), data)
+    }
+
+    /// Returns an error unless the receiver contract returned `Ok(())`.
+    ///
+    /// A receiver which trapped, rejected the token, or returned anything else rejects the token.
+    fn check_acknowledgement(
+      result: ink_env::Result<Result<(), PSP34ReceiverError>>,
+    ) -> Result<(), Error> {
+      match result {
+        Ok(Ok(())) => Ok(()),
+        _ => Err(Error::SafeTransferCheckFailed),
+      }
+    }
+
+    /// Removes token `id` from the owner.
+    fn remove_token_from(&mut self, from: &AccountId, id: TokenId) -> Result<(), Error> {
+      let Self {
+        token_owner,
+        owned_tokens_count,
+        ..
+      } = self;
+
+      if !token_owner.contains(id) {
+        return Err(Error::TokenNotFound);
+      }
+
+      let count = owned_tokens_count
+        .get(from)
+        .map(|c| c - 1)
+        .ok_or(Error::CannotFetchValue)?;
+      owned_tokens_count.insert(from, &count);
+      token_owner.remove(id);
+
+      Ok(())
+    }
+
+    /// Adds the token `id` to the `to` AccountID.
+    fn add_token_to(&mut self, to: &AccountId, id: TokenId) -> Result<(), Error> {
+      let Self {
+        token_owner,
+        owned_tokens_count,
+        ..
+      } = self;
+
+      if *to == AccountId::from([0x0; 32]) {
+        return Err(Error::NotAllowed);
+      };
+
+      let count = owned_tokens_count.get(to).map(|c| c + 1).unwrap_or(1);
+
+      owned_tokens_count.insert(to, &count);
+      token_owner.insert(id, to);
+
+      Ok(())
+    }
+
+    /// Appends the new owner and ephemeral public key to the ownership history of token `id`.
+    fn record_history(
+      &mut self,
+      id: TokenId,
+      owner: AccountId,
+      ephemeral_public_key: Option<EphemeralPublicKey>,
+    ) {
+      let len = self.token_history_len.get(id).unwrap_or(0);
+      let entry = (
+        self.nonce_of(id),
+        owner,
+        ephemeral_public_key,
+        self.env().block_number(),
+      );
+      self.token_history.insert((id, len), &entry);
+      self.token_history_len.insert(id, &(len + 1));
+    }
+
+    /// Adds ephemeral public key to TokenId
+    fn add_ephemeral_public_key(&mut self, id: TokenId, ephemeral_public_key: EphemeralPublicKey) {
+      if self.token_ephemeral.contains(id) {
+        self.token_ephemeral.remove(id);
+      }
+
+      self.token_ephemeral.insert(id, &ephemeral_public_key);
+    }
+
+    /// Approve the passed `AccountId` to transfer the specified token on behalf of the message's sender.
+    ///
+    /// Replaces the existing approval of the token if any.
+    fn approve_for(
+      &mut self,
+      to: &AccountId,
+      id: TokenId,
+      ephemeral_public_key: EphemeralPublicKey,
+      signature: String,
+    ) -> Result<(), Error> {
+      if !self.exists(id) {
+        return Err(Error::TokenNotFound);
+      };
+      if self.is_locked(id) {
+        return Err(Error::TokenLocked);
+      };
+
+      // hash input params
+      let messag_hash = self.hash_message(*to, id, &ephemeral_public_key);
+      // recover signer
+      let signer = self.recover_signer(&messag_hash, &signature)?;
+
+      let owner = self.token_owner.get(id).ok_or(Error::TokenNotFound)?;
+      if owner != signer && !self.approved_for_all(owner, signer) {
+        return Err(Error::NotAllowed);
+      };
+
+      if *to == AccountId::from([0x0; 32]) {
+        return Err(Error::NotAllowed);
+      };
+
+      // Replace the existing approval if any
+      self.token_approvals.insert(id, to);
+
+      self.add_ephemeral_public_key(id, ephemeral_public_key);
+
+      // Update token nonce
+      let nonce = self
+        .token_nonce
+        .get(id)
+        .map(|c| c + 1)
+        .ok_or(Error::CannotFetchValue)?;
+      self.token_nonce.insert(id, &nonce);
+
+      self.env().emit_event(Approval {
+        owner,
+        operator: *to,
+        id: Some(Id::U32(id)),
+        approved: true,
+      });
+
+      Ok(())
+    }
+
+    /// Announces the ephemeral public key of the stealth address of token `id`.
+    fn announce(
+      &self,
+      id: TokenId,
+      stealth_address: AccountId,
+      ephemeral_public_key: EphemeralPublicKey,
+      view_tag: u8,
+    ) {
+      self.env().emit_event(Announcement {
+        id: Id::U32(id),
+        stealth_address,
+        ephemeral_public_key,
+        view_tag,
+      });
+    }
+
+    /// Removes existing approval from token `id`.
+    fn clear_approval(&mut self, id: TokenId) {
+      self.token_approvals.remove(id);
+    }
+
+    // Returns the total number of tokens from an account.
+    fn balance_of_or_zero(&self, id: &AccountId) -> u32 {
+      self.owned_tokens_count.get(id).unwrap_or(0)
+    }
+
+    /// Approves or disapproves the operator for all tokens of the owner.
+    fn approve_operator(&mut self, owner: AccountId, operator: AccountId, approved: bool) {
+      if approved {
+        self.operator_approvals.insert((&owner, &operator), &());
+      } else {
+        self.operator_approvals.remove((&owner, &operator));
+      }
+
+      self.env().emit_event(Approval {
+        owner,
+        operator,
+        id: None,
+        approved,
+      });
+    }
+
+    /// Gets an operator on other Account's behalf.
+    fn approved_for_all(&self, owner: AccountId, operator: AccountId) -> bool {
+      self.operator_approvals.contains((&owner, &operator))
+    }
+
+    /// Returns true if the `AccountId` `from` is the owner of token `id`
+    /// or it has been approved on behalf of the token `id` owner.
+    fn approved_or_owner(&self, from: Option<AccountId>, id: TokenId) -> bool {
+      let owner = self.token_owner.get(id);
+      from != Some(AccountId::from([0x0; 32]))
+        && (from == owner
+          || from == self.token_approvals.get(id)
+          || matches!((owner, from), (Some(owner), Some(from)) if self.approved_for_all(owner, from)))
+    }
+
+    /// Returns true if token `id` is locked, individually or by a soulbound collection.
+    fn is_locked(&self, id: TokenId) -> bool {
+      self.soulbound || self.locked_tokens.contains(id)
+    }
+
+    /// Returns true if token `id` exists or false if it does not.
+    fn exists(&self, id: TokenId) -> bool {
+      self.token_owner.contains(id)
+    }
+  }
+
+  impl PSP34 for Erc721 {
+    /// Returns the collection `Id`, which is the address of this contract.
+    #[ink(message)]
+    fn collection_id(&self) -> Id {
+      let account_id: [u8; 32] = *self.env().account_id().as_ref();
+      Id::Bytes(account_id.to_vec())
+    }
+
+    /// Returns the balance of the owner.
+    ///
+    /// This represents the amount of unique tokens the owner has.
+    #[ink(message)]
+    fn balance_of(&self, owner: AccountId) -> u32 {
+      self.balance_of_or_zero(&owner)
+    }
+
+    /// Returns the owner of the token.
+    #[ink(message)]
+    fn owner_of(&self, id: Id) -> Option<AccountId> {
+      Self::token_id(&id)
+        .ok()
+        .and_then(|id| self.token_owner.get(id))
+    }
+
+    /// Returns `true` if the operator is approved for the token `id`,
+    /// or for all tokens of the owner if `id` is `None`.
+    #[ink(message)]
+    fn allowance(&self, owner: AccountId, operator: AccountId, id: Option<Id>) -> bool {
+      if self.approved_for_all(owner, operator) {
+        return true;
+      }
+
+      match id.map(|id| Self::token_id(&id)) {
+        Some(Ok(id)) => {
+          self.token_owner.get(id) == Some(owner) && self.token_approvals.get(id) == Some(operator)
+        }
+        _ => false,
+      }
+    }
+
+    /// Approves the operator for the token `id`, or for all tokens of the caller if `id` is `None`.
+    ///
+    /// The caller must be the owner or an operator of the owner of the token.
+    #[ink(message)]
+    fn approve(
+      &mut self,
+      operator: AccountId,
+      id: Option<Id>,
+      approved: bool,
+    ) -> Result<(), PSP34Error> {
+      let caller = self.env().caller();
+      if operator == caller {
+        return Err(PSP34Error::SelfApprove);
+      }
+
+      let id = match id {
+        Some(id) => Self::token_id(&id)?,
+        None => {
+          self.approve_operator(caller, operator, approved);
+          return Ok(());
+        }
+      };
+
+      let owner = self.token_owner.get(id).ok_or(Error::TokenNotFound)?;
+      if owner != caller && !self.approved_for_all(owner, caller) {
+        return Err(PSP34Error::NotApproved);
+      }
+
+      if approved {
+        if operator == AccountId::from([0x0; 32]) {
+          return Err(Error::NotAllowed.into());
+        }
+        if self.is_locked(id) {
+          return Err(Error::TokenLocked.into());
+        }
+        self.token_approvals.insert(id, &operator);
+      } else if self.token_approvals.get(id) == Some(operator) {
+        self.clear_approval(id);
+      } else {
+        return Err(PSP34Error::NotApproved);
+      }
+
+      self.env().emit_event(Approval {
+        owner,
+        operator,
+        id: Some(Id::U32(id)),
+        approved,
+      });
+
+      Ok(())
+    }
+
+    /// Transfers the token from the caller to the given `AccountId`.
+    ///
+    /// The recipient is a plain account, so the ephemeral public key of the token is removed.
+    /// If `to` is a contract, it must accept the token by its `PSP34Receiver::before_received` message.
+    #[ink(message)]
+    fn transfer(&mut self, to: AccountId, id: Id, data: Vec<u8>) -> Result<(), PSP34Error> {
+      let id = Self::token_id(&id)?;
+      let caller = self.env().caller();
+      let owner = self.token_owner.get(id).ok_or(Error::TokenNotFound)?;
+      if !self.approved_or_owner(Some(caller), id) {
+        return Err(PSP34Error::NotApproved);
+      }
+
+      self.transfer_token_from(&owner, &to, id, None, Some(data))?;
+
+      Ok(())
+    }
+
+    /// Returns the total supply.
+    #[ink(message)]
+    fn total_supply(&self) -> Balance {
+      self.total_supply as Balance
+    }
+  }
+
+  impl PSP34Metadata for Erc721 {
+    /// Returns the `baseUri` attribute of the collection, the `uri` attribute of a token,
+    /// or an attribute set by `set_attribute`.
+    #[ink(message)]
+    fn get_attribute(&self, id: Id, key: Vec<u8>) -> Option<Vec<u8>> {
+      if id == self.collection_id() {
+        return match key.as_slice() {
+          b"baseUri" => Some(self.base_uri.clone().into_bytes()),
+          _ => self.attributes.get((&id, &key)),
+        };
+      }
+
+      if self.owner_of(id.clone()).is_none() {
+        return None;
+      }
+
+      match key.as_slice() {
+        b"uri" => self.token_uri(id).ok().map(String::into_bytes),
+        _ => self.attributes.get((&id, &key)),
+      }
+    }
+  }
+
+  /// Unit tests
+  #[cfg(test)]
+  mod tests {
+    // Imports all the definitions from the outer scope so we can use them here.
+    use super::*;
+    use ink_lang as ink;
+    use std::cell::RefCell;
+    // Because the test environment does not support elliptic curve APIs, public keys and signatures have to be hard-coded for test purposes.
+    // Alice ephemeral public key.
+    const ALICE_EPHEMERAL_PUBLIC_KEY: &str =
+      "023283ba9bfc9f689cb4ca88d14734aea6e3bdded740d0e560e9344ab4fe825733";
+    const ALICE_ENCRYPTED_ADDRESS_BYTES: [u8; 32] = [
+      16, 106, 116, 36, 96, 18, 235, 9, 152, 150, 66, 41, 227, 178, 97, 58, 215, 25, 214, 129, 221,
+      230, 182, 93, 103, 15, 31, 51, 86, 33, 248, 192,
+    ];
+
+    // Bob ephemeral public key.
+    const BOB_EPHEMERAL_PUBLIC_KEY: &str =
+      "02c5e1752c5f2d858207407c8d1e0b35a3f078a5d80dd3f109bf9ff0bfebd1f449";
+    const BOB_ENCRYPTED_ADDRESS_BYTES: [u8; 32] = [
+      163, 107, 29, 40, 120, 48, 34, 216, 17, 150, 100, 98, 245, 18, 117, 135, 100, 170, 65, 66,
+      168, 150, 165, 125, 46, 117, 207, 171, 186, 216, 207, 200,
+    ];
+
+    // Charlie ephemeral public key.
+    const CHARLIE_EPHEMERAL_PUBLIC_KEY: &str =
+      "02d6ed2824df9dd354c9343a1daf7a93d1f3bc5b6cfcc4150676b70676b1cadff8";
+    const CHARLIE_ENCRYPTED_ADDRESS_BYTES: [u8; 32] = [
+      85, 42, 128, 42, 184, 40, 141, 192, 239, 172, 100, 182, 219, 143, 32, 28, 16, 27, 7, 234,
+      217, 189, 78, 93, 59, 114, 163, 111, 135, 255, 234, 80,
+    ];
+
+    // signature signed by Node.js.
+    const ALICE_APPROVE_TO_BOB_SIGNATURE: &str = "cee1d58cc00c64355a7d2bf9b750e6ed0816e9ebbcc2de35aa2acb06178026c62983d2640c027904e31295378aa6750e6a9a1f2d126d49b642819014faa3d1ab01";
+    const BOB_TRANSFER_TO_CHARLIE_SIGNATURE: &str = "76caa2e333d969e0ea54edffe62dc9c838666730cd8828d4c845b83cbfdaa88a4baa5dfc657fce0f130df546a2b545dedd81d0e1e5ef847939184018b7376b2400";
+
+    // Dave encrypted address and signatures.
+    const DAVE_ENCRYPTED_ADDRESS_BYTES: [u8; 32] = [
+      152, 46, 186, 108, 175, 244, 214, 176, 26, 79, 53, 21, 22, 51, 92, 202, 65, 88, 60, 150, 62,
+      105, 102, 124, 116, 5, 127, 43, 236, 168, 149, 105,
+    ];
+    const DAVE_APPROVE_TO_BOB_SIGNATURE: &str = "78330ceeeb642f603f7053a52169a4bec0f51e58e198f7e0a22d3c941ae4385c31f5f7038e3b3456732fb44483fbd5e439c6021307e6f534cc02ae313991a8c300";
+    const DAVE_APPROVE_TO_CHARLIE_SIGNATURE: &str = "fdb7cfaea79e6c4ae3a0a183ed154a07742f439a934fd6ccef56c30abd1e9bf578e1807874cd08f26ca6ec69aefc118877b946448bf059c52934b5d098b98f2c00";
+    const DAVE_REVOKE_APPROVAL_SIGNATURE: &str = "0cfc1233056f0ae3ff2f822d86184a6325b8835714f40b69aec143626a315b8a00332b73ad260f756260f257df03940eb6518b469fbfcf5373ebaef96511d43c00";
+    const DAVE_REVOKE_APPROVAL_AGAIN_SIGNATURE: &str = "73f0950c0ba13a4444cfed21c8ca326e3cdad6341ec6ab4f1a0d6f6cbc3e3a6f288afdbd61c6d87b2e214ec09bac8abf450fedca4614f60c7ba5428612eef8d201";
+    const DAVE_TRANSFER_TO_EVE_SIGNATURE: &str = "83a611d36d61894f25e8809252ac8b0259e83b65f39103eebea52bcd1d4cc49441c5d9fbcf729522d067bf851236564ffe6e9f67a2a3428c6ed0c098afb74b5201";
+    const DAVE_TRANSFER_TOKEN_2_TO_EVE_SIGNATURE: &str = "0be65d65667cee1103a5ca627e4a8e93817f17bf5df8ffd45601b4e2189c95844bf60a0d03672dd25ff5849db97873a175af77fb739636d72ad0c3383beaf22d01";
+    const DAVE_BURN_SIGNATURE: &str = "3a9111a1732c42c8a3c51d7e8af4c32009dee1ad1eb00d32b088b97aef8a13ad313a4a5d77230286481b76f1e9b919227219b80cfe934872e2261cc0614573c900";
+
+    // Eve encrypted address and signatures.
+    const EVE_ENCRYPTED_ADDRESS_BYTES: [u8; 32] = [
+      44, 89, 151, 107, 206, 150, 44, 236, 245, 238, 219, 93, 21, 202, 142, 17, 188, 163, 254, 187,
+      87, 213, 120, 39, 32, 50, 33, 47, 70, 74, 247, 25,
+    ];
+    const DAVE_APPROVE_EVE_FOR_ALL_SIGNATURE: &str = "9b08394c810ddba7216a5db946b3d11c09f84ba5a181e0136ad596305193dc3b08458857113948a0df28e93fa0fd726e0b81f74d575ac34f23c71b14922edb1300";
+    const DAVE_DISAPPROVE_EVE_FOR_ALL_SIGNATURE: &str = "5a51ade8538c3653527f2eb27f806421e778a2857b2bcb9c7a27dd81a26fbbfe51932270ce91fb79023cee1e91a8795344a4474ddaf0ed16d4790d3e2ba9311401";
+    const EVE_TRANSFER_TO_CHARLIE_SIGNATURE: &str = "86efe7145cd8970b32e28d81ede0fc1ca3b229e764b21a84add1d74d3c2addf63e138ae0b9b63276e530de2f5377a0ef9fbfdbb844dbf6b77625731513b04a8401";
+    const EVE_TRANSFER_TOKEN_2_TO_CHARLIE_SIGNATURE: &str = "1eeee34903bd9c87d07334dcd5441912dfc2feb13e3735f0ceac4fee98ba8bd77e0f231468ff1b83535de26fe15d5f4031a2beb352857bb29b1d66f9d173160101";
+
+    // Frank encrypted address and signatures, signed by the secp256k1 key keccak256("frank").
+    const FRANK_ENCRYPTED_ADDRESS_BYTES: [u8; 32] = [
+      156, 6, 203, 252, 137, 28, 87, 8, 16, 34, 174, 113, 205, 238, 103, 192, 33, 228, 125, 207, 6,
+      234, 207, 95, 175, 149, 254, 153, 11, 86, 208, 182,
+    ];
+    const FRANK_APPROVE_TOKEN_2_TO_BOB_SIGNATURE: &str = "07705486d5497d4c1cd1440e70919313bc6d179cba7c8a7a14a39a76c5c0f25e34212db417191c0b3a06ee9552a2c2a02a6eaeadd45cb041bf9caab6bb4fcea700";
+    const FRANK_BURN_TOKEN_2_SIGNATURE: &str = "632063b8129b135c8b809136a92b4eee92523d266722bb8fabc3e775e3cdddd5139ab1242087d9c69ca706542e86ad77bd30c708ad5fe32cb046e26859b7e4d301";
+
+    const BASE_URI: &str = "https://raw.githubusercontent.com/GreenLemonProtocol/assets/main/nft";
+    const VIEW_TAG: u8 = 0x2a;
+
+    /// The behavior of a mock receiver contract.
+    #[derive(Debug, Clone, Copy)]
+    enum MockReceiver {
+      Accept,
+      Reject,
+      Trap,
+    }
+
+    /// A call of `before_received`: the receiver, operator, sender, id and data.
+    type ReceiverCall = (AccountId, AccountId, AccountId, Id, Vec<u8>);
+
+    thread_local! {
+      static MOCK_RECEIVERS: RefCell<Vec<(AccountId, MockReceiver)>> = RefCell::new(Vec::new());
+      static RECEIVER_CALLS: RefCell<Vec<ReceiverCall>> = RefCell::new(Vec::new());
+      static UPLOADED_CODE: RefCell<Vec<[u8; 32]>> = RefCell::new(Vec::new());
+      static CODE_HASH: RefCell<Option<[u8; 32]>> = RefCell::new(None);
+    }
+
+    /// Makes the account a receiver contract of the test.
+    fn register_mock_receiver(account: AccountId, receiver: MockReceiver) {
+      MOCK_RECEIVERS.with(|receivers| receivers.borrow_mut().push((account, receiver)));
+    }
+
+    fn mock_receiver(account: &AccountId) -> Option<MockReceiver> {
+      MOCK_RECEIVERS.with(|receivers| {
+        receivers
+          .borrow()
+          .iter()
+          .find(|(receiver, _)| receiver == account)
+          .map(|(_, receiver)| *receiver)
+      })
+    }
+
+    fn mock_receiver_calls() -> Vec<ReceiverCall> {
+      RECEIVER_CALLS.with(|calls| calls.borrow().clone())
+    }
+
+    pub fn is_mock_receiver(account: &AccountId) -> bool {
+      mock_receiver(account).is_some()
+    }
+
+    /// Makes the code hash known to the test, like uploaded code.
+    fn upload_mock_code(code_hash: [u8; 32]) {
+      UPLOADED_CODE.with(|code| code.borrow_mut().push(code_hash));
+    }
+
+    fn mock_code_hash() -> Option<[u8; 32]> {
+      CODE_HASH.with(|hash| *hash.borrow())
+    }
+
+    pub fn set_mock_code_hash(code_hash: &[u8; 32]) -> ink_env::Result<()> {
+      if !UPLOADED_CODE.with(|code| code.borrow().contains(code_hash)) {
+        return Err(ink_env::Error::CodeNotFound);
+      }
+      CODE_HASH.with(|hash| *hash.borrow_mut() = Some(*code_hash));
+      Ok(())
+    }
+
+    pub fn call_mock_receiver(
+      to: &AccountId,
+      operator: AccountId,
+      from: AccountId,
+      id: Id,
+      data: Vec<u8>,
+    ) -> ink_env::Result<Result<(), PSP34ReceiverError>> {
+      RECEIVER_CALLS.with(|calls| calls.borrow_mut().push((*to, operator, from, id, data)));
+      match mock_receiver(to) {
+        Some(MockReceiver::Accept) => Ok(Ok(())),
+        Some(MockReceiver::Reject) => Ok(Err(PSP34ReceiverError::TransferRejected(String::from(
+          "rejected",
+        )))),
+        _ => Err(ink_env::Error::CalleeTrapped),
+      }
+    }
+
+    #[ink::test]
+    fn base_uri_works() {
+      // Create a new contract instance.
+      let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+      let mut erc721 = Erc721::new(BASE_URI.to_string());
+      let ephemeral_public_key = ALICE_EPHEMERAL_PUBLIC_KEY.to_string();
+      let nft_id = 1;
+
+      assert_eq!(erc721.token_nonce_of(Id::U32(nft_id)), 0);
+      // Create token Id 1.
+      assert_eq!(
+        erc721.mint(accounts.alice, ephemeral_public_key, VIEW_TAG),
+        Ok(())
+      );
+
+      assert_eq!(erc721.base_uri(), BASE_URI.to_string());
+      assert_eq!(
+        erc721.token_uri(Id::U32(nft_id)),
+        Ok(BASE_URI.to_string() + "/1")
+      );
+      assert_eq!(erc721.token_nonce_of(Id::U32(nft_id)), 1);
+    }
+
+    #[ink::test]
+    fn metadata_works() {
+      let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+      // Create a new contract instance, Alice is the admin.
+      let mut erc721 = Erc721::new(BASE_URI.to_string());
+      assert_eq!(erc721.admin(), accounts.alice);
+
+      // Create token Id 1 and 2.
+      assert_eq!(
+        erc721.mint(
+          accounts.alice,
+          ALICE_EPHEMERAL_PUBLIC_KEY.to_string(),
+          VIEW_TAG
+        ),
+        Ok(())
+      );
+      assert_eq!(
+        erc721.mint(accounts.bob, BOB_EPHEMERAL_PUBLIC_KEY.to_string(), VIEW_TAG),
+        Ok(())
+      );
+
+      // Base URI and suffix.
+      let new_base_uri = "ipfs://collection";
+      assert_eq!(erc721.set_base_uri(new_base_uri.to_string()), Ok(()));
+      assert_eq!(erc721.set_uri_suffix(true), Ok(()));
+      assert_eq!(erc721.base_uri(), new_base_uri.to_string());
+      assert_eq!(
+        erc721.token_uri(Id::U32(1)),
+        Ok(String::from("ipfs://collection/1.json"))
+      );
+
+      // Token URI overrides.
+      assert_eq!(
+        erc721.set_token_uri(Id::U32(2), "ipfs://token".to_string()),
+        Ok(())
+      );
+      assert_eq!(
+        erc721.token_uri(Id::U32(2)),
+        Ok(String::from("ipfs://token"))
+      );
+      assert_eq!(
+        erc721.get_attribute(Id::U32(2), b"uri".to_vec()),
+        Some(b"ipfs://token".to_vec())
+      );
+      assert_eq!(
+        erc721.set_token_uri(Id::U32(3), "ipfs://token".to_string()),
+        Err(Error::TokenNotFound)
+      );
+      assert_eq!(erc721.set_token_uri(Id::U32(2), String::new()), Ok(()));
+      assert_eq!(
+        erc721.token_uri(Id::U32(2)),
+        Ok(String::from("ipfs://collection/2.json"))
+      );
+
+      // Unknown ids have no URI.
+      assert_eq!(erc721.token_uri(Id::U32(3)), Err(Error::TokenNotFound));
+      assert_eq!(erc721.token_uri(Id::U64(1)), Err(Error::TokenNotFound));
+
+      // Attributes.
+      assert_eq!(
+        erc721.set_attribute(Id::U32(1), b"name".to_vec(), b"Lemon".to_vec()),
+        Ok(())
+      );
+      assert_eq!(
+        erc721.set_attribute(erc721.collection_id(), b"name".to_vec(), b"Lemons".to_vec()),
+        Ok(())
+      );
+      assert_eq!(
+        erc721.set_attribute(Id::U32(3), b"name".to_vec(), b"Lemon".to_vec()),
+        Err(Error::TokenNotFound)
+      );
+      assert_eq!(
+        erc721.get_attribute(Id::U32(1), b"name".to_vec()),
+        Some(b"Lemon".to_vec())
+      );
+      assert_eq!(
+        erc721.get_attribute(erc721.collection_id(), b"name".to_vec()),
+        Some(b"Lemons".to_vec())
+      );
+      assert_eq!(erc721.get_attribute(Id::U32(2), b"name".to_vec()), None);
+
+      // Only the admin can change the metadata.
+      ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+      assert_eq!(
+        erc721.set_base_uri(BASE_URI.to_string()),
+        Err(Error::NotAdmin)
+      );
+      assert_eq!(erc721.freeze_metadata(), Err(Error::NotAdmin));
+
+      // The metadata cannot be changed once frozen.
+      ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.alice);
+      assert_eq!(erc721.freeze_metadata(), Ok(()));
+      assert!(erc721.metadata_frozen());
+      assert_eq!(
+        erc721.set_base_uri(BASE_URI.to_string()),
+        Err(Error::MetadataFrozen)
+      );
+      assert_eq!(erc721.set_uri_suffix(false), Err(Error::MetadataFrozen));
+      assert_eq!(
+        erc721.set_token_uri(Id::U32(1), "ipfs://token".to_string()),
+        Err(Error::MetadataFrozen)
+      );
+      assert_eq!(
+        erc721.set_attribute(Id::U32(1), b"name".to_vec(), b"Lime".to_vec()),
+        Err(Error::MetadataFrozen)
+      );
+      assert_eq!(erc721.base_uri(), new_base_uri.to_string());
+    }
+
+    #[ink::test]
+    fn reveal_works() {
+      let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+      let placeholder_uri = "ipfs://placeholder";
+      let salt = [7; 32];
+
+      let mut input = BASE_URI.as_bytes().to_vec();
+      input.extend(salt);
+      let mut provenance_hash: [u8; 32] = [0; 32];
+      ink_env::hash_bytes::<ink_env::hash::Keccak256>(&input, &mut provenance_hash);
+
+      // Create a new contract instance with hidden metadata.
+      let mut erc721 = Erc721::new_hidden(placeholder_uri.to_string(), provenance_hash);
+      assert!(!erc721.revealed());
+      assert_eq!(erc721.provenance_hash(), Some(provenance_hash));
+
+      // Create token Id 1.
+      assert_eq!(
+        erc721.mint(
+          accounts.alice,
+          ALICE_EPHEMERAL_PUBLIC_KEY.to_string(),
+          VIEW_TAG
+        ),
+        Ok(())
+      );
+      assert_eq!(
+        erc721.token_uri(Id::U32(1)),
+        Ok(placeholder_uri.to_string())
+      );
+
+      // The base URI cannot be set before the reveal.
+      assert_eq!(
+        erc721.set_base_uri(BASE_URI.to_string()),
+        Err(Error::NotRevealed)
+      );
+
+      // Only the admin can reveal.
+      ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+      assert_eq!(
+        erc721.reveal(BASE_URI.to_string(), salt),
+        Err(Error::NotAdmin)
+      );
+
+      // The base URI and salt must match the provenance hash.
+      ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.alice);
+      assert_eq!(
+        erc721.reveal(BASE_URI.to_string(), [8; 32]),
+        Err(Error::InvalidProvenance)
+      );
+      assert_eq!(
+        erc721.reveal("ipfs://other".to_string(), salt),
+        Err(Error::InvalidProvenance)
+      );
+      assert_eq!(
+        erc721.token_uri(Id::U32(1)),
+        Ok(placeholder_uri.to_string())
+      );
+
+      assert_eq!(erc721.reveal(BASE_URI.to_string(), salt), Ok(()));
+      assert!(erc721.revealed());
+      assert_eq!(
+        erc721.token_uri(Id::U32(1)),
+        Ok(BASE_URI.to_string() + "/1")
+      );
+
+      // The metadata can only be revealed once.
+      assert_eq!(
+        erc721.reveal(BASE_URI.to_string(), salt),
+        Err(Error::AlreadyRevealed)
+      );
+    }
+
+    #[ink::test]
+    fn announcement_works() {
+      let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+      // Create a new contract instance.
+      let mut erc721 = Erc721::new(BASE_URI.to_string());
+      let ephemeral_public_key = erc721
+        .decode_ephemeral_public_key(&ALICE_EPHEMERAL_PUBLIC_KEY.to_string())
+        .unwrap();
+
+      // Create token Id 1 for Alice.
+      assert_eq!(
+        erc721.mint(
+          accounts.alice,
+          ALICE_EPHEMERAL_PUBLIC_KEY.to_string(),
+          VIEW_TAG
+        ),
+        Ok(())
+      );
+
+      // The mint emits a Transfer and an Announcement event.
+      let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
+      assert_eq!(emitted_events.len(), 2);
+      let decoded_event = <Event as scale::Decode>::decode(&mut &emitted_events[1].data[..])
+        .expect("encountered invalid contract event data buffer");
+      if let Event::Announcement(Announcement {
+        id,
+        stealth_address,
+        ephemeral_public_key: announced_key,
+        view_tag,
+      }) = decoded_event
+      {
+        assert_eq!(id, Id::U32(1));
+        assert_eq!(stealth_address, accounts.alice);
+        assert_eq!(announced_key, ephemeral_public_key);
+        assert_eq!(view_tag, VIEW_TAG);
+      } else {
+        panic!("encountered unexpected event kind: expected an Announcement event")
+      }
+    }
+
+    #[ink::test]
+    fn royalty_works() {
+      let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+      // Create a new contract instance, Alice is the admin.
+      let mut erc721 = Erc721::new(BASE_URI.to_string());
+      let dave_encrypted_address = AccountId::from(DAVE_ENCRYPTED_ADDRESS_BYTES);
+      let zero_address = AccountId::from([0x0; 32]);
+
+      // Create token Id 1 and 2.
+      for _ in 0..2 {
+        assert_eq!(
+          erc721.mint(accounts.bob, BOB_EPHEMERAL_PUBLIC_KEY.to_string(), VIEW_TAG),
+          Ok(())
+        );
+      }
+
+      // No royalty by default.
+      assert_eq!(erc721.royalty_info(Id::U32(1), 1_000), (zero_address, 0));
+
+      // 2.5% to the stealth address of Dave.
+      assert_eq!(
+        erc721.set_default_royalty(
+          dave_encrypted_address,
+          250,
+          Some(ALICE_EPHEMERAL_PUBLIC_KEY.to_string()),
+          VIEW_TAG
+        ),
+        Ok(())
+      );
+      assert_eq!(
+        erc721.royalty_info(Id::U32(1), 1_000),
+        (dave_encrypted_address, 25)
+      );
+      assert_eq!(
+        erc721.royalty_info(Id::U32(2), Balance::MAX),
+        (
+          dave_encrypted_address,
+          Balance::MAX / 10_000 * 250 + 1455 * 250 / 10_000
+        )
+      );
+
+      // 10% of token Id 2 to Charlie.
+      assert_eq!(
+        erc721.set_token_royalty(Id::U32(2), accounts.charlie, 1_000, None, VIEW_TAG),
+        Ok(())
+      );
+      assert_eq!(
+        erc721.royalty_info(Id::U32(2), 1_000),
+        (accounts.charlie, 100)
+      );
+      assert_eq!(
+        erc721.royalty_info(Id::U32(1), 1_000),
+        (dave_encrypted_address, 25)
+      );
+      assert_eq!(
+        erc721.set_token_royalty(
+          Id::U32(3),
+          accounts.charlie,
+          1_000,
+          Some(CHARLIE_EPHEMERAL_PUBLIC_KEY.to_string()),
+          VIEW_TAG
+        ),
+        Err(Error::TokenNotFound)
+      );
+      assert_eq!(
+        erc721.set_token_royalty(
+          Id::U32(2),
+          accounts.charlie,
+          10_001,
+          Some(CHARLIE_EPHEMERAL_PUBLIC_KEY.to_string()),
+          VIEW_TAG
+        ),
+        Err(Error::InvalidRoyalty)
+      );
+      assert_eq!(
+        erc721.set_default_royalty(
+          accounts.charlie,
+          10_001,
+          Some(CHARLIE_EPHEMERAL_PUBLIC_KEY.to_string()),
+          VIEW_TAG
+        ),
+        Err(Error::InvalidRoyalty)
+      );
+      assert_eq!(
+        erc721.set_default_royalty(accounts.charlie, 1_000, Some("04".repeat(33)), VIEW_TAG),
+        Err(Error::InvalidEphemeralKey)
+      );
+
+      // Only the admin can set royalties.
+      ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+      assert_eq!(
+        erc721.set_default_royalty(
+          accounts.bob,
+          1_000,
+          Some(CHARLIE_EPHEMERAL_PUBLIC_KEY.to_string()),
+          VIEW_TAG
+        ),
+        Err(Error::NotAdmin)
+      );
+      assert_eq!(erc721.reset_token_royalty(Id::U32(2)), Err(Error::NotAdmin));
+
+      ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.alice);
+      assert_eq!(
+        erc721.reset_token_royalty(Id::U32(3)),
+        Err(Error::TokenNotFound)
+      );
+      assert_eq!(erc721.reset_token_royalty(Id::U32(2)), Ok(()));
+      assert_eq!(
+        erc721.royalty_info(Id::U32(2), 1_000),
+        (dave_encrypted_address, 25)
+      );
+    }
+
+    #[ink::test]
+    fn royalty_announcement_works() {
+      let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+      // Create a new contract instance, Alice is the admin.
+      let mut erc721 = Erc721::new(BASE_URI.to_string());
+      let dave_encrypted_address = AccountId::from(DAVE_ENCRYPTED_ADDRESS_BYTES);
+      let ephemeral_public_key = erc721
+        .decode_ephemeral_public_key(&ALICE_EPHEMERAL_PUBLIC_KEY.to_string())
+        .unwrap();
+
+      // Create token Id 1.
+      assert_eq!(
+        erc721.mint(accounts.bob, BOB_EPHEMERAL_PUBLIC_KEY.to_string(), VIEW_TAG),
+        Ok(())
+      );
+
+      // The royalties are paid to the stealth address of Dave.
+      assert_eq!(
+        erc721.set_default_royalty(
+          dave_encrypted_address,
+          250,
+          Some(ALICE_EPHEMERAL_PUBLIC_KEY.to_string()),
+          VIEW_TAG
+        ),
+        Ok(())
+      );
+      assert_eq!(
+        erc721.set_token_royalty(
+          Id::U32(1),
+          dave_encrypted_address,
+          1_000,
+          Some(ALICE_EPHEMERAL_PUBLIC_KEY.to_string()),
+          VIEW_TAG
+        ),
+        Ok(())
+      );
+      assert_eq!(erc721.reset_token_royalty(Id::U32(1)), Ok(()));
+
+      // Charlie is not a stealth address, so there is nothing to announce.
+      assert_eq!(
+        erc721.set_token_royalty(Id::U32(1), accounts.charlie, 500, None, VIEW_TAG),
+        Ok(())
+      );
+
+      // The mint emits 2 events, setting a royalty emits a RoyaltySet and an Announcement event.
+      let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
+      assert_eq!(emitted_events.len(), 8);
+      let decode = |index: usize| {
+        <Event as scale::Decode>::decode(&mut &emitted_events[index].data[..])
+          .expect("encountered invalid contract event data buffer")
+      };
+      let expected = [
+        (
+          None,
+          Some((dave_encrypted_address, 250)),
+          Id::U32(DEFAULT_ROYALTY_ID),
+        ),
+        (
+          Some(Id::U32(1)),
+          Some((dave_encrypted_address, 1_000)),
+          Id::U32(1),
+        ),
+      ];
+      for (index, (royalty_id, royalty_set, announced_id)) in expected.into_iter().enumerate() {
+        if let Event::RoyaltySet(RoyaltySet { id, royalty }) = decode(2 + 2 * index) {
+          assert_eq!(id, royalty_id);
+          assert_eq!(royalty, royalty_set);
+        } else {
+          panic!("encountered unexpected event kind: expected a RoyaltySet event")
+        }
+        if let Event::Announcement(Announcement {
+          id,
+          stealth_address,
+          ephemeral_public_key: announced_key,
+          view_tag,
+        }) = decode(3 + 2 * index)
+        {
+          assert_eq!(id, announced_id);
+          assert_eq!(stealth_address, dave_encrypted_address);
+          assert_eq!(announced_key, ephemeral_public_key);
+          assert_eq!(view_tag, VIEW_TAG);
+        } else {
+          panic!("encountered unexpected event kind: expected an Announcement event")
+        }
+      }
+
+      // Resetting the royalty of a token emits a RoyaltySet event only.
+      if let Event::RoyaltySet(RoyaltySet { id, royalty }) = decode(6) {
+        assert_eq!(id, Some(Id::U32(1)));
+        assert_eq!(royalty, None);
+      } else {
+        panic!("encountered unexpected event kind: expected a RoyaltySet event")
+      }
+      if let Event::RoyaltySet(RoyaltySet { id, royalty }) = decode(7) {
+        assert_eq!(id, Some(Id::U32(1)));
+        assert_eq!(royalty, Some((accounts.charlie, 500)));
+      } else {
+        panic!("encountered unexpected event kind: expected a RoyaltySet event")
+      }
+    }
+
+    #[ink::test]
+    fn soulbound_works() {
+      let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+      // Create a new soulbound contract instance.
+      let mut erc721 = Erc721::new_soulbound(BASE_URI.to_string());
+      let dave_encrypted_address = AccountId::from(DAVE_ENCRYPTED_ADDRESS_BYTES);
+
+      assert_eq!(erc721.locked(Id::U32(1)), Err(Error::TokenNotFound));
+
+      // Create token Id 1 for Dave and token Id 2 for Alice.
+      assert_eq!(
+        erc721.mint(
+          dave_encrypted_address,
+          ALICE_EPHEMERAL_PUBLIC_KEY.to_string(),
+          VIEW_TAG
+        ),
+        Ok(())
+      );
+      assert_eq!(
+        erc721.mint(
+          accounts.alice,
+          ALICE_EPHEMERAL_PUBLIC_KEY.to_string(),
+          VIEW_TAG
+        ),
+        Ok(())
+      );
+      assert_eq!(erc721.locked(Id::U32(1)), Ok(true));
+
+      // Tokens cannot be transferred or approved.
+      assert_eq!(
+        erc721.stealth_approve(
+          accounts.bob,
+          Id::U32(1),
+          BOB_EPHEMERAL_PUBLIC_KEY.to_string(),
+          VIEW_TAG,
+          DAVE_APPROVE_TO_BOB_SIGNATURE.to_string()
+        ),
+        Err(Error::TokenLocked)
+      );
+      assert_eq!(
+        erc721.transfer(accounts.bob, Id::U32(2), Vec::new()),
+        Err(PSP34Error::Custom(String::from("TokenLocked")))
+      );
+      assert_eq!(
+        erc721.approve(accounts.bob, Some(Id::U32(2)), true),
+        Err(PSP34Error::Custom(String::from("TokenLocked")))
+      );
+      assert_eq!(
+        erc721.set_locked(Id::U32(2), false),
+        Err(Error::TokenLocked)
+      );
+
+      // Tokens can be burned.
+      assert_eq!(
+        erc721.burn(Id::U32(1), DAVE_BURN_SIGNATURE.to_string()),
+        Ok(())
+      );
+      assert_eq!(erc721.locked(Id::U32(1)), Err(Error::TokenNotFound));
+    }
+
+    #[ink::test]
+    fn lock_token_works() {
+      let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+      // Create a new contract instance, Alice is the admin.
+      let mut erc721 = Erc721::new(BASE_URI.to_string());
+
+      // Create token Id 1 for Alice.
+      assert_eq!(
+        erc721.mint(
+          accounts.alice,
+          ALICE_EPHEMERAL_PUBLIC_KEY.to_string(),
+          VIEW_TAG
+        ),
+        Ok(())
+      );
+      assert_eq!(erc721.locked(Id::U32(1)), Ok(false));
+
+      // Only the admin can lock tokens.
+      ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+      assert_eq!(erc721.set_locked(Id::U32(1), true), Err(Error::NotAdmin));
+
+      ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.alice);
+      assert_eq!(erc721.set_locked(Id::U32(1), true), Ok(()));
+      assert_eq!(erc721.locked(Id::U32(1)), Ok(true));
+      assert_eq!(
+        erc721.safe_transfer(accounts.bob, Id::U32(1), Vec::new()),
+        Err(Error::TokenLocked)
+      );
+
+      // Unlocked tokens can be transferred again.
+      assert_eq!(erc721.set_locked(Id::U32(1), false), Ok(()));
+      assert_eq!(
+        erc721.transfer(accounts.bob, Id::U32(1), Vec::new()),
+        Ok(())
+      );
+      assert_eq!(erc721.owner_of(Id::U32(1)), Some(accounts.bob));
+    }
+
+    #[ink::test]
+    fn ownership_works() {
+      let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+      // Create a new contract instance, Alice is the admin.
+      let mut erc721 = Erc721::new(BASE_URI.to_string());
+      assert_eq!(erc721.storage_version(), STORAGE_VERSION);
+      assert_eq!(erc721.pending_admin(), None);
+
+      // Only the admin can transfer the ownership.
+      ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+      assert_eq!(
+        erc721.transfer_ownership(accounts.bob),
+        Err(Error::NotAdmin)
+      );
+      assert_eq!(erc721.set_code_hash([0x1; 32]), Err(Error::NotAdmin));
+
+      // Alice proposes Bob, but only Bob can accept.
+      ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.alice);
+      assert_eq!(
+        erc721.transfer_ownership(AccountId::from([0x0; 32])),
+        Err(Error::NotAllowed)
+      );
+      assert_eq!(erc721.transfer_ownership(accounts.bob), Ok(()));
+      assert_eq!(erc721.pending_admin(), Some(accounts.bob));
+      assert_eq!(erc721.accept_ownership(), Err(Error::NotPendingAdmin));
+      assert_eq!(erc721.admin(), accounts.alice);
+
+      ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+      assert_eq!(erc721.accept_ownership(), Ok(()));
+      assert_eq!(erc721.admin(), accounts.bob);
+      assert_eq!(erc721.pending_admin(), None);
+
+      // Proposing and accepting the admin role emit an event each.
+      let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
+      assert_eq!(emitted_events.len(), 2);
+      let decode = |index: usize| {
+        <Event as scale::Decode>::decode(&mut &emitted_events[index].data[..])
+          .expect("encountered invalid contract event data buffer")
+      };
+      if let Event::OwnershipTransferStarted(OwnershipTransferStarted {
+        previous_admin,
+        new_admin,
+      }) = decode(0)
+      {
+        assert_eq!(previous_admin, accounts.alice);
+        assert_eq!(new_admin, accounts.bob);
+      } else {
+        panic!("encountered unexpected event kind: expected an OwnershipTransferStarted event")
+      }
+      if let Event::OwnershipTransferred(OwnershipTransferred {
+        previous_admin,
+        new_admin,
+      }) = decode(1)
+      {
+        assert_eq!(previous_admin, accounts.alice);
+        assert_eq!(new_admin, accounts.bob);
+      } else {
+        panic!("encountered unexpected event kind: expected an OwnershipTransferred event")
+      }
+      assert_eq!(erc721.set_base_uri(BASE_URI.to_string()), Ok(()));
+
+      // Alice is no longer the admin.
+      ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.alice);
+      assert_eq!(
+        erc721.set_base_uri(BASE_URI.to_string()),
+        Err(Error::NotAdmin)
+      );
+    }
+
+    #[ink::test]
+    fn set_code_hash_works() {
+      let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+      // Create a new contract instance, Alice is the admin.
+      let mut erc721 = Erc721::new(BASE_URI.to_string());
+      assert_eq!(
+        erc721.mint(
+          accounts.alice,
+          ALICE_EPHEMERAL_PUBLIC_KEY.to_string(),
+          VIEW_TAG
+        ),
+        Ok(())
+      );
+      upload_mock_code([0x1; 32]);
+
+      // Code which has not been uploaded cannot be set.
+      assert_eq!(erc721.set_code_hash([0x2; 32]), Err(Error::UpgradeFailed));
+      assert_eq!(mock_code_hash(), None);
+
+      // Only the admin can upgrade the contract.
+      ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+      assert_eq!(erc721.set_code_hash([0x1; 32]), Err(Error::NotAdmin));
+      assert_eq!(mock_code_hash(), None);
+
+      ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.alice);
+      assert_eq!(erc721.set_code_hash([0x1; 32]), Ok(()));
+      assert_eq!(mock_code_hash(), Some([0x1; 32]));
+
+      // The upgrade emits an Upgraded event after the 2 events of the mint.
+      let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
+      assert_eq!(emitted_events.len(), 3);
+      if let Event::Upgraded(Upgraded { code_hash }) =
+        <Event as scale::Decode>::decode(&mut &emitted_events[2].data[..])
+          .expect("encountered invalid contract event data buffer")
+      {
+        assert_eq!(code_hash, [0x1; 32]);
+      } else {
+        panic!("encountered unexpected event kind: expected an Upgraded event")
+      }
+
+      // The storage is kept.
+      assert_eq!(erc721.storage_version(), STORAGE_VERSION);
+      assert_eq!(erc721.owner_of(Id::U32(1)), Some(accounts.alice));
+    }
+
+    #[ink::test]
+    fn mint() {
+      let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+      // Create a new contract instance.
+      let mut erc721 = Erc721::new(BASE_URI.to_string());
+      let ephemeral_public_key = ALICE_EPHEMERAL_PUBLIC_KEY.to_string();
+      let nft_id = 1;
+
+      // Token 1 does not exists.
+      assert_eq!(erc721.owner_of(Id::U32(1)), None);
+      assert_eq!(erc721.token_nonce_of(Id::U32(nft_id)), 0);
+
+      // Alice does not owns tokens.
+      assert_eq!(erc721.balance_of(accounts.alice), 0);
+
+      // Total supply = 0
+      assert_eq!(erc721.total_supply(), 0);
+
+      // Create token Id 1.
+      assert_eq!(
+        erc721.mint(accounts.alice, ephemeral_public_key.clone(), VIEW_TAG),
+        Ok(())
+      );
+
+      // Total supply = 1
+      assert_eq!(erc721.total_supply(), 1);
+
+      // Owner owns 1 token.
+      assert_eq!(erc721.balance_of(accounts.alice), nft_id);
+      assert_eq!(
+        erc721.ephemeral_public_key_of(Id::U32(nft_id)),
+        erc721
+          .decode_ephemeral_public_key(&ephemeral_public_key)
+          .ok()
+      );
+
+      // Owner owns NFT 1.
+      assert_eq!(erc721.owner_of(Id::U32(nft_id)), Some(accounts.alice));
+      assert_eq!(erc721.token_nonce_of(Id::U32(nft_id)), 1);
+
+      // Create token Id 2.
+      assert_eq!(
+        erc721.mint(accounts.alice, ephemeral_public_key, VIEW_TAG),
+        Ok(())
+      );
+
+      // Alice balance equal 2
+      assert_eq!(erc721.balance_of(accounts.alice), 2);
+    }
+
+    #[ink::test]
+    fn transfer_from() {
+      // Create a new contract instance.
+      let mut erc721 = Erc721::new(BASE_URI.to_string());
+      let alice_ephemeral_public_key = ALICE_EPHEMERAL_PUBLIC_KEY.to_string();
+      let alice_encrypted_address = AccountId::from(ALICE_ENCRYPTED_ADDRESS_BYTES);
+
+      let bob_ephemeral_public_key = BOB_EPHEMERAL_PUBLIC_KEY.to_string();
+      let bob_encrypted_address = AccountId::from(BOB_ENCRYPTED_ADDRESS_BYTES);
+
+      let charlie_ephemeral_public_key = CHARLIE_EPHEMERAL_PUBLIC_KEY.to_string();
+      let charlie_encrypted_address = AccountId::from(CHARLIE_ENCRYPTED_ADDRESS_BYTES);
+      let nft_id = 1;
+      // Create token Id 1 for Alice.
+      assert_eq!(
+        erc721.mint(
+          alice_encrypted_address,
+          alice_ephemeral_public_key,
+          VIEW_TAG
+        ),
+        Ok(())
+      );
+
+      // Alice owns token 1.
+      assert_eq!(erc721.balance_of(alice_encrypted_address), 1);
+      assert_eq!(erc721.token_nonce_of(Id::U32(nft_id)), 1);
+
+      // Alice approves Bob to transfer token 1.
+      assert_eq!(
+        erc721.stealth_approve(
+          bob_encrypted_address,
+          Id::U32(nft_id),
+          bob_ephemeral_public_key,
+          VIEW_TAG,
+          ALICE_APPROVE_TO_BOB_SIGNATURE.to_string()
+        ),
+        Ok(())
+      );
+      assert_eq!(erc721.token_nonce_of(Id::U32(nft_id)), 2);
+
+      // Check Bob approved by Alice
+      assert_eq!(
+        erc721.get_approved(Id::U32(nft_id)),
+        Some(bob_encrypted_address)
+      );
+
+      // Bob transfer token Id 1 should work
+      assert_eq!(
+        erc721.transfer_from(
+          alice_encrypted_address,
+          charlie_encrypted_address,
+          Id::U32(nft_id),
+          charlie_ephemeral_public_key,
+          VIEW_TAG,
+          BOB_TRANSFER_TO_CHARLIE_SIGNATURE.to_string()
+        ),
+        Ok(())
+      );
+
+      // Owner owns NFT 1.
+      assert_eq!(
+        erc721.owner_of(Id::U32(nft_id)),
+        Some(charlie_encrypted_address)
+      );
+      assert_eq!(erc721.token_nonce_of(Id::U32(nft_id)), 3);
+    }
+
+    #[ink::test]
+    fn approve_and_transfer() {
+      // Create a new contract instance.
+      let mut erc721 = Erc721::new(BASE_URI.to_string());
+      let alice_ephemeral_public_key = ALICE_EPHEMERAL_PUBLIC_KEY.to_string();
+      let alice_encrypted_address = AccountId::from(ALICE_ENCRYPTED_ADDRESS_BYTES);
+
+      let bob_ephemeral_public_key = BOB_EPHEMERAL_PUBLIC_KEY.to_string();
+      let bob_encrypted_address = AccountId::from(BOB_ENCRYPTED_ADDRESS_BYTES);
+
+      let charlie_ephemeral_public_key = CHARLIE_EPHEMERAL_PUBLIC_KEY.to_string();
+      let charlie_encrypted_address = AccountId::from(CHARLIE_ENCRYPTED_ADDRESS_BYTES);
+      let nft_id = 1;
+      // Create token Id 1 for Alice.
+      assert_eq!(
+        erc721.mint(
+          alice_encrypted_address,
+          alice_ephemeral_public_key.clone(),
+          VIEW_TAG
+        ),
+        Ok(())
+      );
+
+      // Create token Id 2 for Charlie.
+      assert_eq!(
+        erc721.mint(
+          charlie_encrypted_address,
+          charlie_ephemeral_public_key.clone(),
+          VIEW_TAG
+        ),
+        Ok(())
+      );
+
+      // Total supply = 2
+      assert_eq!(erc721.total_supply(), 2);
+
+      // Alice owns token 1.
+      assert_eq!(erc721.balance_of(alice_encrypted_address), 1);
+      assert_eq!(erc721.token_nonce_of(Id::U32(nft_id)), 1);
+
+      // Bob transfer token Id 1 should fail
+      assert_eq!(
+        erc721.stealth_transfer(
+          charlie_encrypted_address,
+          Id::U32(1),
+          bob_ephemeral_public_key.clone(),
+          VIEW_TAG,
+          BOB_TRANSFER_TO_CHARLIE_SIGNATURE.to_string()
+        ),
+        Err(Error::NotApproved)
+      );
+
+      // Bob approves Alice to transfer token 1.
+      assert_eq!(
+        erc721.stealth_approve(
+          alice_encrypted_address,
+          Id::U32(2),
+          alice_ephemeral_public_key,
+          VIEW_TAG,
+          ALICE_APPROVE_TO_BOB_SIGNATURE.to_string()
+        ),
+        Err(Error::NotAllowed)
+      );
+
+      // Alice approves Bob to transfer token 1.
+      assert_eq!(
+        erc721.stealth_approve(
+          bob_encrypted_address,
+          Id::U32(nft_id),
+          bob_ephemeral_public_key,
+          VIEW_TAG,
+          ALICE_APPROVE_TO_BOB_SIGNATURE.to_string()
+        ),
+        Ok(())
+      );
+      assert_eq!(erc721.token_nonce_of(Id::U32(nft_id)), 2);
+
+      // Check Bob approved by Alice
+      assert_eq!(
+        erc721.get_approved(Id::U32(nft_id)),
+        Some(bob_encrypted_address)
+      );
+
+      // Bob transfer token Id 1 should work
+      assert_eq!(
+        erc721.transfer_from(
+          alice_encrypted_address,
+          charlie_encrypted_address,
+          Id::U32(nft_id),
+          charlie_ephemeral_public_key,
+          VIEW_TAG,
+          BOB_TRANSFER_TO_CHARLIE_SIGNATURE.to_string()
+        ),
+        Ok(())
+      );
+
+      // Owner owns NFT 1.
+      assert_eq!(
+        erc721.owner_of(Id::U32(nft_id)),
+        Some(charlie_encrypted_address)
+      );
+      assert_eq!(erc721.token_nonce_of(Id::U32(nft_id)), 3);
+    }
+
+    #[ink::test]
+    fn enumerable_works() {
+      let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+      // Create a new contract instance.
+      let mut erc721 = Erc721::new(BASE_URI.to_string());
+      let dave_encrypted_address = AccountId::from(DAVE_ENCRYPTED_ADDRESS_BYTES);
+      let alice_ephemeral_public_key = erc721
+        .decode_ephemeral_public_key(&ALICE_EPHEMERAL_PUBLIC_KEY.to_string())
+        .unwrap();
+      let bob_ephemeral_public_key = erc721
+        .decode_ephemeral_public_key(&BOB_EPHEMERAL_PUBLIC_KEY.to_string())
+        .unwrap();
+
+      assert_eq!(erc721.token_by_index(0), None);
+      assert_eq!(erc721.tokens(0, 10), Vec::new());
+
+      // Create token Id 1 for Dave, token Id 2 for Bob and token Id 3 for Charlie.
+      assert_eq!(
+        erc721.mint(
+          dave_encrypted_address,
+          ALICE_EPHEMERAL_PUBLIC_KEY.to_string(),
+          VIEW_TAG
+        ),
+        Ok(())
+      );
+      assert_eq!(
+        erc721.mint(accounts.bob, BOB_EPHEMERAL_PUBLIC_KEY.to_string(), VIEW_TAG),
+        Ok(())
+      );
+      assert_eq!(
+        erc721.mint(
+          accounts.charlie,
+          ALICE_EPHEMERAL_PUBLIC_KEY.to_string(),
+          VIEW_TAG
+        ),
+        Ok(())
+      );
+
+      assert_eq!(erc721.token_by_index(0), Some(1));
+      assert_eq!(erc721.token_by_index(2), Some(3));
+      assert_eq!(erc721.token_by_index(3), None);
+      assert_eq!(
+        erc721.tokens(1, 10),
+        vec![
+          (2, accounts.bob, Some(bob_ephemeral_public_key), 1),
+          (3, accounts.charlie, Some(alice_ephemeral_public_key), 1),
+        ]
+      );
+      assert_eq!(erc721.tokens(0, 1).len(), 1);
+      assert_eq!(erc721.tokens(3, 1), Vec::new());
+      assert_eq!(erc721.tokens(u32::MAX, u32::MAX), Vec::new());
+
+      // Dave burns token Id 1, token Id 3 takes its index.
+      assert_eq!(
+        erc721.burn(Id::U32(1), DAVE_BURN_SIGNATURE.to_string()),
+        Ok(())
+      );
+      assert_eq!(erc721.token_by_index(0), Some(3));
+      assert_eq!(erc721.token_by_index(1), Some(2));
+      assert_eq!(erc721.token_by_index(2), None);
+
+      // The Id of a burned token is not reused.
+      assert_eq!(
+        erc721.mint(
+          accounts.django,
+          BOB_EPHEMERAL_PUBLIC_KEY.to_string(),
+          VIEW_TAG
+        ),
+        Ok(())
+      );
+      assert_eq!(erc721.token_by_index(2), Some(4));
+      assert_eq!(erc721.tokens(0, 10).len(), 3);
+      assert_eq!(
+        erc721.owners_of(vec![1, 2, 4]),
+        vec![None, Some(accounts.bob), Some(accounts.django)]
+      );
+    }
+
+    #[ink::test]
+    fn token_history_works() {
+      let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+      // Create a new contract instance.
+      let mut erc721 = Erc721::new(BASE_URI.to_string());
+      let ephemeral_public_key = erc721
+        .decode_ephemeral_public_key(&ALICE_EPHEMERAL_PUBLIC_KEY.to_string())
+        .unwrap();
+
+      assert_eq!(erc721.token_history(Id::U32(1), 0, 10), Vec::new());
+
+      // Create token Id 1 for Alice.
+      assert_eq!(
+        erc721.mint(
+          accounts.alice,
+          ALICE_EPHEMERAL_PUBLIC_KEY.to_string(),
+          VIEW_TAG
+        ),
+        Ok(())
+      );
+
+      // Alice transfers token Id 1 to Bob in the next block, then Bob to Charlie.
+      ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+      assert_eq!(
+        erc721.transfer(accounts.bob, Id::U32(1), Vec::new()),
+        Ok(())
+      );
+      ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+      assert_eq!(
+        erc721.transfer(accounts.charlie, Id::U32(1), Vec::new()),
+        Ok(())
+      );
+
+      assert_eq!(
+        erc721.token_history(Id::U32(1), 0, 10),
+        vec![
+          (1, accounts.alice, Some(ephemeral_public_key), 0),
+          (2, accounts.bob, None, 1),
+          (3, accounts.charlie, None, 1),
+        ]
+      );
+      assert_eq!(
+        erc721.token_history(Id::U32(1), 1, 1),
+        vec![(2, accounts.bob, None, 1)]
+      );
+      assert_eq!(erc721.token_history(Id::U32(1), 3, 10), Vec::new());
+      assert_eq!(erc721.token_history(Id::U64(1), 0, 10), Vec::new());
+    }
+
+    #[ink::test]
+    fn transfer_batch_works() {
+      // Create a new contract instance.
+      let mut erc721 = Erc721::new(BASE_URI.to_string());
+      let dave_encrypted_address = AccountId::from(DAVE_ENCRYPTED_ADDRESS_BYTES);
+      let eve_encrypted_address = AccountId::from(EVE_ENCRYPTED_ADDRESS_BYTES);
+
+      // Create token Id 1 and 2 for Dave.
+      for _ in 0..2 {
+        assert_eq!(
+          erc721.mint(
+            dave_encrypted_address,
+            ALICE_EPHEMERAL_PUBLIC_KEY.to_string(),
+            VIEW_TAG
+          ),
+          Ok(())
+        );
+      }
+
+      let transfer_1 = (
+        eve_encrypted_address,
+        Id::U32(1),
+        BOB_EPHEMERAL_PUBLIC_KEY.to_string(),
+        VIEW_TAG,
+        DAVE_TRANSFER_TO_EVE_SIGNATURE.to_string(),
+      );
+      let transfer_2 = (
+        eve_encrypted_address,
+        Id::U32(2),
+        CHARLIE_EPHEMERAL_PUBLIC_KEY.to_string(),
+        VIEW_TAG,
+        DAVE_TRANSFER_TOKEN_2_TO_EVE_SIGNATURE.to_string(),
+      );
+
+      // A wrong signature fails the whole batch.
+      let mut wrong_transfer_2 = transfer_2.clone();
+      wrong_transfer_2.4 = DAVE_TRANSFER_TO_EVE_SIGNATURE.to_string();
+      assert_eq!(
+        erc721.transfer_batch(vec![transfer_1.clone(), wrong_transfer_2]),
+        Err(Error::NotApproved)
+      );
+
+      // The same token cannot be transferred twice.
+      assert_eq!(
+        erc721.transfer_batch(vec![transfer_1.clone(), transfer_1.clone()]),
+        Err(Error::NotAllowed)
+      );
+
+      // Nothing changed.
+      assert_eq!(erc721.balance_of(dave_encrypted_address), 2);
+      assert_eq!(erc721.token_nonce_of(Id::U32(1)), 1);
+
+      // Dave transfers both tokens to Eve.
+      assert_eq!(erc721.transfer_batch(vec![transfer_1, transfer_2]), Ok(()));
+      assert_eq!(erc721.balance_of(dave_encrypted_address), 0);
+      assert_eq!(erc721.balance_of(eve_encrypted_address), 2);
+      assert_eq!(
+        erc721.owners_of(vec![1, 2]),
+        vec![Some(eve_encrypted_address), Some(eve_encrypted_address)]
+      );
+      assert_eq!(erc721.token_nonce_of(Id::U32(2)), 2);
+      assert_eq!(erc721.transfer_batch(Vec::new()), Ok(()));
+    }
+
+    #[ink::test]
+    fn operator_transfer_batch_works() {
+      // Create a new contract instance.
+      let mut erc721 = Erc721::new(BASE_URI.to_string());
+      let dave_encrypted_address = AccountId::from(DAVE_ENCRYPTED_ADDRESS_BYTES);
+      let eve_encrypted_address = AccountId::from(EVE_ENCRYPTED_ADDRESS_BYTES);
+      let charlie_encrypted_address = AccountId::from(CHARLIE_ENCRYPTED_ADDRESS_BYTES);
+
+      // Create token Id 1 and 2 for Dave.
+      for _ in 0..2 {
+        assert_eq!(
+          erc721.mint(
+            dave_encrypted_address,
+            ALICE_EPHEMERAL_PUBLIC_KEY.to_string(),
+            VIEW_TAG
+          ),
+          Ok(())
+        );
+      }
+
+      let transfers = vec![
+        (
+          charlie_encrypted_address,
+          Id::U32(1),
+          CHARLIE_EPHEMERAL_PUBLIC_KEY.to_string(),
+          VIEW_TAG,
+          EVE_TRANSFER_TO_CHARLIE_SIGNATURE.to_string(),
+        ),
+        (
+          charlie_encrypted_address,
+          Id::U32(2),
+          CHARLIE_EPHEMERAL_PUBLIC_KEY.to_string(),
+          VIEW_TAG,
+          EVE_TRANSFER_TOKEN_2_TO_CHARLIE_SIGNATURE.to_string(),
+        ),
+      ];
+
+      // Eve is not approved yet.
+      assert_eq!(
+        erc721.transfer_batch(transfers.clone()),
+        Err(Error::NotApproved)
+      );
+
+      // Dave approves Eve as operator.
+      assert_eq!(
+        erc721.set_approval_for_all(
+          dave_encrypted_address,
+          eve_encrypted_address,
+          true,
+          DAVE_APPROVE_EVE_FOR_ALL_SIGNATURE.to_string()
+        ),
+        Ok(())
+      );
+
+      // Eve transfers both tokens of Dave to Charlie.
+      assert_eq!(erc721.transfer_batch(transfers), Ok(()));
+      assert_eq!(erc721.balance_of(dave_encrypted_address), 0);
+      assert_eq!(erc721.balance_of(charlie_encrypted_address), 2);
+      assert_eq!(
+        erc721.owners_of(vec![1, 2]),
+        vec![
+          Some(charlie_encrypted_address),
+          Some(charlie_encrypted_address)
+        ]
+      );
+    }
+
+    #[ink::test]
+    fn safe_transfer_fails_before_receiver_check() {
+      let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+      // Create a new contract instance.
+      let mut erc721 = Erc721::new(BASE_URI.to_string());
+
+      // Create token Id 1 for Alice.
+      assert_eq!(
+        erc721.mint(
+          accounts.alice,
+          ALICE_EPHEMERAL_PUBLIC_KEY.to_string(),
+          VIEW_TAG
+        ),
+        Ok(())
+      );
+
+      // The checks done before asking the receiver.
+      assert_eq!(
+        erc721.safe_transfer(accounts.bob, Id::U32(2), Vec::new()),
+        Err(Error::TokenNotFound)
+      );
+      assert_eq!(
+        erc721.safe_transfer(AccountId::from([0x0; 32]), Id::U32(1), Vec::new()),
+        Err(Error::NotAllowed)
+      );
+      ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+      assert_eq!(
+        erc721.safe_transfer(accounts.bob, Id::U32(1), Vec::new()),
+        Err(Error::NotApproved)
+      );
+      assert_eq!(
+        erc721.safe_stealth_transfer(
+          accounts.bob,
+          Id::U32(1),
+          BOB_EPHEMERAL_PUBLIC_KEY.to_string(),
+          VIEW_TAG,
+          "00".repeat(65),
+          Vec::new()
+        ),
+        Err(Error::InvalidSignature)
+      );
+      assert_eq!(
+        PSP34Error::from(Error::SafeTransferCheckFailed),
+        PSP34Error::SafeTransferCheckFailed(String::from("receiver rejected the token"))
+      );
+      assert_eq!(erc721.owner_of(Id::U32(1)), Some(accounts.alice));
+    }
+
+    #[ink::test]
+    fn safe_transfer_to_contract_works() {
+      let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+      // Create a new contract instance.
+      let mut erc721 = Erc721::new(BASE_URI.to_string());
+
+      // Create token Id 1 and 2 for Alice.
+      for _ in 0..2 {
+        assert_eq!(
+          erc721.mint(
+            accounts.alice,
+            ALICE_EPHEMERAL_PUBLIC_KEY.to_string(),
+            VIEW_TAG
+          ),
+          Ok(())
+        );
+      }
+
+      // Bob is a contract rejecting tokens, Charlie one which traps, and Django one accepting them.
+      register_mock_receiver(accounts.bob, MockReceiver::Reject);
+      register_mock_receiver(accounts.charlie, MockReceiver::Trap);
+      register_mock_receiver(accounts.django, MockReceiver::Accept);
+      let data = vec![0x1, 0x2, 0x3];
+      for receiver in [accounts.bob, accounts.charlie] {
+        assert_eq!(
+          erc721.safe_transfer(receiver, Id::U32(1), data.clone()),
+          Err(Error::SafeTransferCheckFailed)
+        );
+      }
+      assert_eq!(erc721.owner_of(Id::U32(1)), Some(accounts.alice));
+      assert_eq!(erc721.token_nonce_of(Id::U32(1)), 1);
+
+      assert_eq!(
+        erc721.safe_transfer(accounts.django, Id::U32(1), data.clone()),
+        Ok(())
+      );
+      assert_eq!(erc721.owner_of(Id::U32(1)), Some(accounts.django));
+
+      // Each receiver was asked by `before_received` with the operator, sender, id and data.
+      let call = |receiver| {
+        (
+          receiver,
+          accounts.alice,
+          accounts.alice,
+          Id::U32(1),
+          data.clone(),
+        )
+      };
+      let calls = vec![
+        call(accounts.bob),
+        call(accounts.charlie),
+        call(accounts.django),
+      ];
+      assert_eq!(mock_receiver_calls(), calls);
+
+      // Accounts which are not contracts accept the token without being asked.
+      assert_eq!(erc721.safe_transfer(accounts.eve, Id::U32(2), data), Ok(()));
+      assert_eq!(erc721.owner_of(Id::U32(2)), Some(accounts.eve));
+      assert_eq!(mock_receiver_calls(), calls);
+    }
+
+    #[ink::test]
+    fn psp34_approve_and_transfer() {
+      let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+      // Create a new contract instance.
+      let mut erc721 = Erc721::new(BASE_URI.to_string());
+      let nft_id = Id::U32(1);
+
+      // Create token Id 1 for Alice.
+      assert_eq!(
+        erc721.mint(
+          accounts.alice,
+          ALICE_EPHEMERAL_PUBLIC_KEY.to_string(),
+          VIEW_TAG
+        ),
+        Ok(())
+      );
+
+      // Bob cannot transfer token Id 1 of Alice.
+      ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+      assert_eq!(
+        erc721.transfer(accounts.charlie, nft_id.clone(), Vec::new()),
+        Err(PSP34Error::NotApproved)
+      );
+
+      // Alice cannot approve herself.
+      ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.alice);
+      assert_eq!(
+        erc721.approve(accounts.alice, Some(nft_id.clone()), true),
+        Err(PSP34Error::SelfApprove)
+      );
+
+      // Alice approves Bob to transfer token Id 1.
+      assert_eq!(
+        erc721.approve(accounts.bob, Some(nft_id.clone()), true),
+        Ok(())
+      );
+      assert!(erc721.allowance(accounts.alice, accounts.bob, Some(nft_id.clone())));
+      assert!(!erc721.allowance(accounts.alice, accounts.bob, None));
+
+      // Bob transfers token Id 1 to Charlie.
+      ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+      assert_eq!(
+        erc721.transfer(accounts.charlie, nft_id.clone(), Vec::new()),
+        Ok(())
+      );
+      assert_eq!(erc721.owner_of(nft_id.clone()), Some(accounts.charlie));
+      assert_eq!(erc721.balance_of(accounts.alice), 0);
+      assert_eq!(erc721.get_approved(nft_id.clone()), None);
+
+      // Charlie is not a stealth address, so the ephemeral public key is removed.
+      assert_eq!(erc721.ephemeral_public_key_of(nft_id.clone()), None);
+      assert_eq!(erc721.token_nonce_of(nft_id.clone()), 2);
 
-    // Charlie ephemeral public key.
-    const CHARLIE_EPHEMERAL_PUBLIC_KEY: &str =
-      "02d6ed2824df9dd354c9343a1daf7a93d1f3bc5b6cfcc4150676b70676b1cadff8";
-    const CHARLIE_ENCRYPTED_ADDRESS_BYTES: [u8; 32] = [
-      85, 42, 128, 42, 184, 40, 141, 192, 239, 172, 100, 182, 219, 143, 32, 28, 16, 27, 7, 234,
-      217, 189, 78, 93, 59, 114, 163, 111, 135, 255, 234, 80,
-    ];
+      // Charlie approves Alice for all tokens.
+      ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.charlie);
+      assert_eq!(erc721.approve(accounts.alice, None, true), Ok(()));
+      assert!(erc721.allowance(accounts.charlie, accounts.alice, None));
+      assert!(erc721.allowance(accounts.charlie, accounts.alice, Some(nft_id.clone())));
 
-    // signature signed by Node.js.
-    const ALICE_APPROVE_TO_BOB_SIGNATURE: &str = "cee1d58cc00c64355a7d2bf9b750e6ed0816e9ebbcc2de35aa2acb06178026c62983d2640c027904e31295378aa6750e6a9a1f2d126d49b642819014faa3d1ab01";
-    const BOB_TRANSFER_TO_CHARLIE_SIGNATURE: &str = "76caa2e333d969e0ea54edffe62dc9c838666730cd8828d4c845b83cbfdaa88a4baa5dfc657fce0f130df546a2b545dedd81d0e1e5ef847939184018b7376b2400";
+      // Alice transfers token Id 1 back as an operator.
+      ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.alice);
+      assert_eq!(
+        erc721.transfer(accounts.alice, nft_id.clone(), Vec::new()),
+        Ok(())
+      );
+      assert_eq!(erc721.owner_of(nft_id.clone()), Some(accounts.alice));
 
-    const BASE_URI: &str = "https://raw.githubusercontent.com/GreenLemonProtocol/assets/main/nft";
+      // A contract must accept the token.
+      register_mock_receiver(accounts.django, MockReceiver::Reject);
+      assert_eq!(
+        erc721.transfer(accounts.django, nft_id.clone(), vec![0x1]),
+        Err(PSP34Error::SafeTransferCheckFailed(String::from(
+          "receiver rejected the token"
+        )))
+      );
+      assert_eq!(erc721.owner_of(nft_id.clone()), Some(accounts.alice));
+      assert_eq!(
+        mock_receiver_calls(),
+        vec![(
+          accounts.django,
+          accounts.alice,
+          accounts.alice,
+          nft_id,
+          vec![0x1]
+        )]
+      );
+
+      // Unknown ids do not exist.
+      assert_eq!(
+        erc721.transfer(accounts.bob, Id::U64(1), Vec::new()),
+        Err(PSP34Error::TokenNotExists)
+      );
+    }
 
     #[ink::test]
-    fn base_uri_works() {
-      // Create a new contract instance.
+    fn psp34_metadata_works() {
       let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+      // Create a new contract instance.
       let mut erc721 = Erc721::new(BASE_URI.to_string());
-      let ephemeral_public_key = ALICE_EPHEMERAL_PUBLIC_KEY.to_string();
-      let nft_id = 1;
 
-      assert_eq!(erc721.token_nonce_of(nft_id), 0);
       // Create token Id 1.
-      assert_eq!(erc721.mint(accounts.alice, ephemeral_public_key), Ok(()));
+      assert_eq!(
+        erc721.mint(
+          accounts.alice,
+          ALICE_EPHEMERAL_PUBLIC_KEY.to_string(),
+          VIEW_TAG
+        ),
+        Ok(())
+      );
 
-      assert_eq!(erc721.base_uri(), BASE_URI.to_string());
-      assert_eq!(erc721.token_uri(nft_id), BASE_URI.to_string() + "/1");
-      assert_eq!(erc721.token_nonce_of(nft_id), 1);
+      assert_eq!(
+        erc721.get_attribute(erc721.collection_id(), b"baseUri".to_vec()),
+        Some(BASE_URI.as_bytes().to_vec())
+      );
+      assert_eq!(
+        erc721.get_attribute(Id::U32(1), b"uri".to_vec()),
+        Some((BASE_URI.to_string() + "/1").into_bytes())
+      );
+      assert_eq!(erc721.get_attribute(Id::U32(2), b"uri".to_vec()), None);
+      assert_eq!(erc721.get_attribute(Id::U32(1), b"name".to_vec()), None);
     }
 
     #[ink::test]
-    fn mint() {
-      let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+    fn replace_and_revoke_approval() {
       // Create a new contract instance.
       let mut erc721 = Erc721::new(BASE_URI.to_string());
-      let ephemeral_public_key = ALICE_EPHEMERAL_PUBLIC_KEY.to_string();
+      let dave_encrypted_address = AccountId::from(DAVE_ENCRYPTED_ADDRESS_BYTES);
+      let bob_encrypted_address = AccountId::from(BOB_ENCRYPTED_ADDRESS_BYTES);
+      let charlie_encrypted_address = AccountId::from(CHARLIE_ENCRYPTED_ADDRESS_BYTES);
       let nft_id = 1;
 
-      // Token 1 does not exists.
-      assert_eq!(erc721.owner_of(1), None);
-      assert_eq!(erc721.token_nonce_of(nft_id), 0);
-
-      // Alice does not owns tokens.
-      assert_eq!(erc721.balance_of(accounts.alice), 0);
-
-      // Total supply = 0
-      assert_eq!(erc721.total_supply(), 0);
-
-      // Create token Id 1.
+      // Create token Id 1 for Dave.
       assert_eq!(
-        erc721.mint(accounts.alice, ephemeral_public_key.clone()),
+        erc721.mint(
+          dave_encrypted_address,
+          ALICE_EPHEMERAL_PUBLIC_KEY.to_string(),
+          VIEW_TAG
+        ),
         Ok(())
       );
 
-      // Total supply = 1
-      assert_eq!(erc721.total_supply(), 1);
+      // Dave approves Bob to transfer token 1.
+      assert_eq!(
+        erc721.stealth_approve(
+          bob_encrypted_address,
+          Id::U32(nft_id),
+          BOB_EPHEMERAL_PUBLIC_KEY.to_string(),
+          VIEW_TAG,
+          DAVE_APPROVE_TO_BOB_SIGNATURE.to_string()
+        ),
+        Ok(())
+      );
+      assert_eq!(
+        erc721.get_approved(Id::U32(nft_id)),
+        Some(bob_encrypted_address)
+      );
 
-      // Owner owns 1 token.
-      assert_eq!(erc721.balance_of(accounts.alice), nft_id);
+      // Dave replaces the approval of Bob by Charlie.
+      assert_eq!(
+        erc721.stealth_approve(
+          charlie_encrypted_address,
+          Id::U32(nft_id),
+          CHARLIE_EPHEMERAL_PUBLIC_KEY.to_string(),
+          VIEW_TAG,
+          DAVE_APPROVE_TO_CHARLIE_SIGNATURE.to_string()
+        ),
+        Ok(())
+      );
       assert_eq!(
-        erc721.ephemeral_public_key_of(nft_id),
-        Some(ephemeral_public_key.clone())
+        erc721.get_approved(Id::U32(nft_id)),
+        Some(charlie_encrypted_address)
       );
+      assert_eq!(erc721.token_nonce_of(Id::U32(nft_id)), 3);
 
-      // Owner owns NFT 1.
-      assert_eq!(erc721.owner_of(nft_id), Some(accounts.alice));
-      assert_eq!(erc721.token_nonce_of(nft_id), 1);
+      // Revoking with a signature of another account should fail.
+      assert_eq!(
+        erc721.revoke_approval(Id::U32(nft_id), ALICE_APPROVE_TO_BOB_SIGNATURE.to_string()),
+        Err(Error::NotAllowed)
+      );
 
-      // Create token Id 2.
-      assert_eq!(erc721.mint(accounts.alice, ephemeral_public_key), Ok(()));
+      // Dave revokes the approval.
+      assert_eq!(
+        erc721.revoke_approval(Id::U32(nft_id), DAVE_REVOKE_APPROVAL_SIGNATURE.to_string()),
+        Ok(())
+      );
+      assert_eq!(erc721.get_approved(Id::U32(nft_id)), None);
+      assert_eq!(erc721.token_nonce_of(Id::U32(nft_id)), 4);
 
-      // Alice balance equal 2
-      assert_eq!(erc721.balance_of(accounts.alice), 2);
+      // There is no approval left to revoke.
+      assert_eq!(
+        erc721.revoke_approval(
+          Id::U32(nft_id),
+          DAVE_REVOKE_APPROVAL_AGAIN_SIGNATURE.to_string()
+        ),
+        Err(Error::NotApproved)
+      );
+      assert_eq!(erc721.token_nonce_of(Id::U32(nft_id)), 4);
     }
 
     #[ink::test]
-    fn transfer_from() {
+    fn approved_for_all_works() {
       // Create a new contract instance.
       let mut erc721 = Erc721::new(BASE_URI.to_string());
-      let alice_ephemeral_public_key = ALICE_EPHEMERAL_PUBLIC_KEY.to_string();
-      let alice_encrypted_address = AccountId::from(ALICE_ENCRYPTED_ADDRESS_BYTES);
-
-      let bob_ephemeral_public_key = BOB_EPHEMERAL_PUBLIC_KEY.to_string();
-      let bob_encrypted_address = AccountId::from(BOB_ENCRYPTED_ADDRESS_BYTES);
-
-      let charlie_ephemeral_public_key = CHARLIE_EPHEMERAL_PUBLIC_KEY.to_string();
+      let dave_encrypted_address = AccountId::from(DAVE_ENCRYPTED_ADDRESS_BYTES);
+      let eve_encrypted_address = AccountId::from(EVE_ENCRYPTED_ADDRESS_BYTES);
       let charlie_encrypted_address = AccountId::from(CHARLIE_ENCRYPTED_ADDRESS_BYTES);
-      let nft_id = 1;
-      // Create token Id 1 for Alice.
+
+      // Create token Id 1 and 2 for Dave.
+      assert_eq!(
+        erc721.mint(
+          dave_encrypted_address,
+          ALICE_EPHEMERAL_PUBLIC_KEY.to_string(),
+          VIEW_TAG
+        ),
+        Ok(())
+      );
       assert_eq!(
-        erc721.mint(alice_encrypted_address, alice_ephemeral_public_key),
+        erc721.mint(
+          dave_encrypted_address,
+          ALICE_EPHEMERAL_PUBLIC_KEY.to_string(),
+          VIEW_TAG
+        ),
         Ok(())
       );
+      assert_eq!(erc721.operator_nonce_of(dave_encrypted_address), 0);
 
-      // Alice owns token 1.
-      assert_eq!(erc721.balance_of(alice_encrypted_address), 1);
-      assert_eq!(erc721.token_nonce_of(nft_id), 1);
+      // The signature of Dave cannot approve an operator for Eve.
+      assert_eq!(
+        erc721.set_approval_for_all(
+          eve_encrypted_address,
+          eve_encrypted_address,
+          true,
+          DAVE_APPROVE_EVE_FOR_ALL_SIGNATURE.to_string()
+        ),
+        Err(Error::NotOwner)
+      );
 
-      // Alice approves Bob to transfer token 1.
+      // Dave approves Eve as operator.
       assert_eq!(
-        erc721.approve(
-          bob_encrypted_address,
-          nft_id,
-          bob_ephemeral_public_key,
-          ALICE_APPROVE_TO_BOB_SIGNATURE.to_string()
+        erc721.set_approval_for_all(
+          dave_encrypted_address,
+          eve_encrypted_address,
+          true,
+          DAVE_APPROVE_EVE_FOR_ALL_SIGNATURE.to_string()
         ),
         Ok(())
       );
-      assert_eq!(erc721.token_nonce_of(nft_id), 2);
-
-      // Check Bob approved by Alice
-      assert_eq!(erc721.get_approved(nft_id), Some(bob_encrypted_address));
+      assert!(erc721.is_approved_for_all(dave_encrypted_address, eve_encrypted_address));
+      assert_eq!(erc721.operator_nonce_of(dave_encrypted_address), 1);
 
-      // Bob transfer token Id 1 should work
+      // Eve transfers token Id 1 of Dave to Charlie.
       assert_eq!(
         erc721.transfer_from(
-          alice_encrypted_address,
+          dave_encrypted_address,
           charlie_encrypted_address,
-          nft_id,
-          charlie_ephemeral_public_key,
-          BOB_TRANSFER_TO_CHARLIE_SIGNATURE.to_string()
+          Id::U32(1),
+          CHARLIE_EPHEMERAL_PUBLIC_KEY.to_string(),
+          VIEW_TAG,
+          EVE_TRANSFER_TO_CHARLIE_SIGNATURE.to_string()
         ),
         Ok(())
       );
+      assert_eq!(erc721.owner_of(Id::U32(1)), Some(charlie_encrypted_address));
+      assert_eq!(erc721.balance_of(dave_encrypted_address), 1);
 
-      // Owner owns NFT 1.
-      assert_eq!(erc721.owner_of(nft_id), Some(charlie_encrypted_address));
-      assert_eq!(erc721.token_nonce_of(nft_id), 3);
+      // Dave disapproves Eve.
+      assert_eq!(
+        erc721.set_approval_for_all(
+          dave_encrypted_address,
+          eve_encrypted_address,
+          false,
+          DAVE_DISAPPROVE_EVE_FOR_ALL_SIGNATURE.to_string()
+        ),
+        Ok(())
+      );
+      assert!(!erc721.is_approved_for_all(dave_encrypted_address, eve_encrypted_address));
+
+      // Eve cannot transfer token Id 2 anymore.
+      assert_eq!(
+        erc721.transfer_from(
+          dave_encrypted_address,
+          charlie_encrypted_address,
+          Id::U32(2),
+          CHARLIE_EPHEMERAL_PUBLIC_KEY.to_string(),
+          VIEW_TAG,
+          EVE_TRANSFER_TOKEN_2_TO_CHARLIE_SIGNATURE.to_string()
+        ),
+        Err(Error::NotApproved)
+      );
+      assert_eq!(erc721.owner_of(Id::U32(2)), Some(dave_encrypted_address));
     }
 
     #[ink::test]
-    fn approve_and_transfer() {
+    fn malformed_ephemeral_public_key_fails() {
       // Create a new contract instance.
       let mut erc721 = Erc721::new(BASE_URI.to_string());
-      let alice_ephemeral_public_key = ALICE_EPHEMERAL_PUBLIC_KEY.to_string();
       let alice_encrypted_address = AccountId::from(ALICE_ENCRYPTED_ADDRESS_BYTES);
-
-      let bob_ephemeral_public_key = BOB_EPHEMERAL_PUBLIC_KEY.to_string();
       let bob_encrypted_address = AccountId::from(BOB_ENCRYPTED_ADDRESS_BYTES);
-
-      let charlie_ephemeral_public_key = CHARLIE_EPHEMERAL_PUBLIC_KEY.to_string();
-      let charlie_encrypted_address = AccountId::from(CHARLIE_ENCRYPTED_ADDRESS_BYTES);
       let nft_id = 1;
+
       // Create token Id 1 for Alice.
       assert_eq!(
-        erc721.mint(alice_encrypted_address, alice_ephemeral_public_key.clone()),
+        erc721.mint(
+          alice_encrypted_address,
+          ALICE_EPHEMERAL_PUBLIC_KEY.to_string(),
+          VIEW_TAG
+        ),
         Ok(())
       );
 
-      // Create token Id 2 for Charlie.
+      // Invalid hex character.
       assert_eq!(
-        erc721.mint(
-          charlie_encrypted_address,
-          charlie_ephemeral_public_key.clone()
+        erc721.stealth_approve(
+          bob_encrypted_address,
+          Id::U32(nft_id),
+          BOB_EPHEMERAL_PUBLIC_KEY.replace('c', "x"),
+          VIEW_TAG,
+          ALICE_APPROVE_TO_BOB_SIGNATURE.to_string()
         ),
-        Ok(())
+        Err(Error::InvalidHexCharacter)
       );
 
-      // Total supply = 2
-      assert_eq!(erc721.total_supply(), 2);
+      // Odd length.
+      assert_eq!(
+        erc721.stealth_approve(
+          bob_encrypted_address,
+          Id::U32(nft_id),
+          BOB_EPHEMERAL_PUBLIC_KEY[1..].to_string(),
+          VIEW_TAG,
+          ALICE_APPROVE_TO_BOB_SIGNATURE.to_string()
+        ),
+        Err(Error::OddLength)
+      );
 
-      // Alice owns token 1.
-      assert_eq!(erc721.balance_of(alice_encrypted_address), 1);
-      assert_eq!(erc721.token_nonce_of(nft_id), 1);
+      // 32 bytes instead of 33 bytes.
+      assert_eq!(
+        erc721.stealth_approve(
+          bob_encrypted_address,
+          Id::U32(nft_id),
+          BOB_EPHEMERAL_PUBLIC_KEY[2..].to_string(),
+          VIEW_TAG,
+          ALICE_APPROVE_TO_BOB_SIGNATURE.to_string()
+        ),
+        Err(Error::InvalidEphemeralKey)
+      );
 
-      // Bob transfer token Id 1 should fail
+      // Nothing changed.
+      assert_eq!(erc721.get_approved(Id::U32(nft_id)), None);
+      assert_eq!(erc721.token_nonce_of(Id::U32(nft_id)), 1);
+    }
+
+    #[ink::test]
+    fn invalid_ephemeral_public_key_fails() {
+      // Create a new contract instance.
+      let mut erc721 = Erc721::new(BASE_URI.to_string());
+      let alice_encrypted_address = AccountId::from(ALICE_ENCRYPTED_ADDRESS_BYTES);
+      let bob_encrypted_address = AccountId::from(BOB_ENCRYPTED_ADDRESS_BYTES);
+      let nft_id = 1;
+
+      // Uncompressed prefix.
       assert_eq!(
-        erc721.transfer(
-          charlie_encrypted_address,
-          1,
-          bob_ephemeral_public_key.clone(),
-          BOB_TRANSFER_TO_CHARLIE_SIGNATURE.to_string()
+        erc721.mint(
+          alice_encrypted_address,
+          ALICE_EPHEMERAL_PUBLIC_KEY.replacen("02", "04", 1),
+          VIEW_TAG
         ),
-        Err(Error::NotApproved)
+        Err(Error::InvalidEphemeralKey)
       );
 
-      // Bob approves Alice to transfer token 1.
+      // 32 bytes instead of 33 bytes.
       assert_eq!(
-        erc721.approve(
+        erc721.mint(
           alice_encrypted_address,
-          2,
-          alice_ephemeral_public_key,
-          ALICE_APPROVE_TO_BOB_SIGNATURE.to_string()
+          ALICE_EPHEMERAL_PUBLIC_KEY[2..].to_string(),
+          VIEW_TAG
         ),
-        Err(Error::NotAllowed)
+        Err(Error::InvalidEphemeralKey)
       );
 
-      // Alice approves Bob to transfer token 1.
+      // Nothing minted.
+      assert_eq!(erc721.total_supply(), 0);
+      assert_eq!(erc721.ephemeral_public_key_of(Id::U32(nft_id)), None);
+
+      // Create token Id 1 for Alice.
+      assert_eq!(
+        erc721.mint(
+          alice_encrypted_address,
+          ALICE_EPHEMERAL_PUBLIC_KEY.to_string(),
+          VIEW_TAG
+        ),
+        Ok(())
+      );
+
+      // Transfer with an invalid prefix.
       assert_eq!(
-        erc721.approve(
+        erc721.stealth_transfer(
           bob_encrypted_address,
-          nft_id,
-          bob_ephemeral_public_key,
+          Id::U32(nft_id),
+          BOB_EPHEMERAL_PUBLIC_KEY.replacen("02", "05", 1),
+          VIEW_TAG,
           ALICE_APPROVE_TO_BOB_SIGNATURE.to_string()
         ),
-        Ok(())
+        Err(Error::InvalidEphemeralKey)
+      );
+      assert_eq!(
+        erc721.owner_of(Id::U32(nft_id)),
+        Some(alice_encrypted_address)
       );
-      assert_eq!(erc721.token_nonce_of(nft_id), 2);
+    }
 
-      // Check Bob approved by Alice
-      assert_eq!(erc721.get_approved(nft_id), Some(bob_encrypted_address));
+    #[ink::test]
+    fn malformed_signature_fails() {
+      // Create a new contract instance.
+      let mut erc721 = Erc721::new(BASE_URI.to_string());
+      let alice_encrypted_address = AccountId::from(ALICE_ENCRYPTED_ADDRESS_BYTES);
+      let bob_encrypted_address = AccountId::from(BOB_ENCRYPTED_ADDRESS_BYTES);
+      let nft_id = 1;
 
-      // Bob transfer token Id 1 should work
+      // Create token Id 1 for Alice.
       assert_eq!(
-        erc721.transfer_from(
+        erc721.mint(
           alice_encrypted_address,
-          charlie_encrypted_address,
-          nft_id,
-          charlie_ephemeral_public_key,
-          BOB_TRANSFER_TO_CHARLIE_SIGNATURE.to_string()
+          ALICE_EPHEMERAL_PUBLIC_KEY.to_string(),
+          VIEW_TAG
         ),
         Ok(())
       );
 
-      // Owner owns NFT 1.
-      assert_eq!(erc721.owner_of(nft_id), Some(charlie_encrypted_address));
-      assert_eq!(erc721.token_nonce_of(nft_id), 3);
+      // Invalid hex character.
+      assert_eq!(
+        erc721.stealth_approve(
+          bob_encrypted_address,
+          Id::U32(nft_id),
+          BOB_EPHEMERAL_PUBLIC_KEY.to_string(),
+          VIEW_TAG,
+          ALICE_APPROVE_TO_BOB_SIGNATURE.replace('c', "x")
+        ),
+        Err(Error::InvalidHexCharacter)
+      );
+
+      // Odd length.
+      assert_eq!(
+        erc721.burn(
+          Id::U32(nft_id),
+          ALICE_APPROVE_TO_BOB_SIGNATURE[1..].to_string()
+        ),
+        Err(Error::OddLength)
+      );
+
+      // 64 bytes instead of 65 bytes.
+      assert_eq!(
+        erc721.stealth_transfer(
+          bob_encrypted_address,
+          Id::U32(nft_id),
+          BOB_EPHEMERAL_PUBLIC_KEY.to_string(),
+          VIEW_TAG,
+          ALICE_APPROVE_TO_BOB_SIGNATURE[2..].to_string()
+        ),
+        Err(Error::InvalidSignature)
+      );
+
+      // The signer cannot be recovered from a zero signature.
+      assert_eq!(
+        erc721.burn(Id::U32(nft_id), "00".repeat(65)),
+        Err(Error::InvalidSignature)
+      );
+
+      // Nothing changed.
+      assert_eq!(
+        erc721.owner_of(Id::U32(nft_id)),
+        Some(alice_encrypted_address)
+      );
+      assert_eq!(erc721.token_nonce_of(Id::U32(nft_id)), 1);
     }
 
     #[ink::test]
@@ -804,7 +3817,7 @@ pub mod erc721 {
 
       // Try burning a non existent token.
       assert_eq!(
-        erc721.burn(1, alice_burn_signature.to_string()),
+        erc721.burn(Id::U32(1), alice_burn_signature.to_string()),
         Err(Error::TokenNotFound)
       );
 
@@ -813,20 +3826,27 @@ pub mod erc721 {
 
       // Create token Id 1 for Alice.
       assert_eq!(
-        erc721.mint(alice_encrtyped_address, alice_ephemeral_public_key),
+        erc721.mint(
+          alice_encrtyped_address,
+          alice_ephemeral_public_key,
+          VIEW_TAG
+        ),
         Ok(())
       );
 
       // Alice owns token 1.
-      assert_eq!(erc721.token_nonce_of(nft_id), 1);
+      assert_eq!(erc721.token_nonce_of(Id::U32(nft_id)), 1);
       assert_eq!(erc721.balance_of(alice_encrtyped_address), 1);
 
       // Owner owns NFT 1.
-      assert_eq!(erc721.owner_of(nft_id), Some(alice_encrtyped_address));
+      assert_eq!(
+        erc721.owner_of(Id::U32(nft_id)),
+        Some(alice_encrtyped_address)
+      );
 
       // Try burning this token with a wrong signature.
       assert_eq!(
-        erc721.burn(1, ALICE_APPROVE_TO_BOB_SIGNATURE.to_string()),
+        erc721.burn(Id::U32(1), ALICE_APPROVE_TO_BOB_SIGNATURE.to_string()),
         Err(Error::NotOwner)
       );
 
@@ -834,17 +3854,49 @@ pub mod erc721 {
       assert_eq!(erc721.total_supply(), 1);
 
       // Burn token Id 1.
-      assert_eq!(erc721.burn(1, alice_burn_signature), Ok(()));
+      assert_eq!(erc721.burn(Id::U32(1), alice_burn_signature), Ok(()));
 
       // Alice does not owns tokens.
       assert_eq!(erc721.balance_of(alice_encrtyped_address), 0);
 
       // Token Id 1 does not exists.
-      assert_eq!(erc721.owner_of(1), None);
-      assert_eq!(erc721.token_nonce_of(nft_id), 0);
+      assert_eq!(erc721.owner_of(Id::U32(1)), None);
+      assert_eq!(erc721.token_nonce_of(Id::U32(nft_id)), 0);
 
       // Total supply = 0
       assert_eq!(erc721.total_supply(), 0);
+
+      // Create token Id 2 for Frank, who approves Bob.
+      let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+      let frank_encrypted_address = AccountId::from(FRANK_ENCRYPTED_ADDRESS_BYTES);
+      assert_eq!(
+        erc721.mint(
+          frank_encrypted_address,
+          ALICE_EPHEMERAL_PUBLIC_KEY.to_string(),
+          VIEW_TAG
+        ),
+        Ok(())
+      );
+      assert_eq!(
+        erc721.stealth_approve(
+          accounts.bob,
+          Id::U32(2),
+          BOB_EPHEMERAL_PUBLIC_KEY.to_string(),
+          VIEW_TAG,
+          FRANK_APPROVE_TOKEN_2_TO_BOB_SIGNATURE.to_string()
+        ),
+        Ok(())
+      );
+      assert_eq!(erc721.get_approved(Id::U32(2)), Some(accounts.bob));
+      assert!(erc721.ephemeral_public_key_of(Id::U32(2)).is_some());
+
+      // Burning the token clears its approval and ephemeral public key.
+      assert_eq!(
+        erc721.burn(Id::U32(2), FRANK_BURN_TOKEN_2_SIGNATURE.to_string()),
+        Ok(())
+      );
+      assert_eq!(erc721.get_approved(Id::U32(2)), None);
+      assert_eq!(erc721.ephemeral_public_key_of(Id::U32(2)), None);
     }
   }
 }
@@ -0,0 +1,124 @@
+//! Traits and types of the [PSP34](https://github.com/w3f/PSPs/blob/master/PSPs/psp-34.md) standard.
+//!
+//! The messages are defined by ink! trait definitions, so their selectors match the ones
+//! expected by Polkadot wallets and marketplaces speaking PSP34.
+//! `PSP34Receiver` is the receiver hook of openbrush's PSP34, so openbrush receivers accept
+//! the safe transfers of this contract.
+
+use ink_env::{AccountId, DefaultEnvironment, Environment};
+use ink_lang as ink;
+use ink_prelude::{string::String, vec::Vec};
+use scale::{Decode, Encode};
+
+/// The balance type of the default environment.
+pub type Balance = <DefaultEnvironment as Environment>::Balance;
+
+/// A PSP34 token ID.
+#[derive(Encode, Decode, Debug, PartialEq, Eq, PartialOrd, Ord, Clone)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub enum Id {
+  U8(u8),
+  U16(u16),
+  U32(u32),
+  U64(u64),
+  U128(u128),
+  Bytes(Vec<u8>),
+}
+
+/// The PSP34 error type.
+#[derive(Encode, Decode, Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub enum PSP34Error {
+  /// Custom error type for cases if writer of traits added own restrictions.
+  Custom(String),
+  /// Returned if owner approves self.
+  SelfApprove,
+  /// Returned if the caller doesn't have allowance for transferring.
+  NotApproved,
+  /// Returned if the owner already own the token.
+  TokenExists,
+  /// Returned if the token doesn't exist.
+  TokenNotExists,
+  /// Returned if safe transfer check fails.
+  SafeTransferCheckFailed(String),
+}
+
+/// The PSP34 standard messages.
+#[ink::trait_definition]
+pub trait PSP34 {
+  /// Returns the collection `Id` of the NFT token.
+  #[ink(message)]
+  fn collection_id(&self) -> Id;
+
+  /// Returns the balance of the owner.
+  ///
+  /// This represents the amount of unique tokens the owner has.
+  #[ink(message)]
+  fn balance_of(&self, owner: AccountId) -> u32;
+
+  /// Returns the owner of the token if any.
+  #[ink(message)]
+  fn owner_of(&self, id: Id) -> Option<AccountId>;
+
+  /// Returns `true` if the operator is approved by the owner to withdraw `id` token.
+  /// If `id` is `None`, returns `true` if the operator is approved to withdraw all owner's tokens.
+  #[ink(message)]
+  fn allowance(&self, owner: AccountId, operator: AccountId, id: Option<Id>) -> bool;
+
+  /// Approves `operator` to withdraw the `id` token from the caller's account.
+  /// If `id` is `None` approves or disapproves the operator for all tokens of the caller.
+  #[ink(message)]
+  fn approve(
+    &mut self,
+    operator: AccountId,
+    id: Option<Id>,
+    approved: bool,
+  ) -> Result<(), PSP34Error>;
+
+  /// Transfer approved or owned token from caller.
+  #[ink(message)]
+  fn transfer(&mut self, to: AccountId, id: Id, data: Vec<u8>) -> Result<(), PSP34Error>;
+
+  /// Returns current NFT total supply.
+  #[ink(message)]
+  fn total_supply(&self) -> Balance;
+}
+
+/// The selector of `PSP34Receiver::before_received`.
+pub const BEFORE_RECEIVED_SELECTOR: [u8; 4] = [0xbb, 0x7d, 0xf7, 0x80];
+
+/// The error of a PSP34 receiver, as in openbrush.
+#[derive(Encode, Decode, Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub enum PSP34ReceiverError {
+  /// Custom error type for cases if writer of traits added own restrictions.
+  Custom(String),
+  /// Returned if the receiver rejects the transfer.
+  TransferRejected(String),
+}
+
+/// The receiver hook of contracts accepting PSP34 tokens by safe transfers, as in openbrush.
+#[ink::trait_definition]
+pub trait PSP34Receiver {
+  /// Called before the token `id` is transferred to the contract by `operator`.
+  ///
+  /// Returns `Ok(())` to accept the token, otherwise the transfer fails.
+  #[ink(message)]
+  fn before_received(
+    &mut self,
+    operator: AccountId,
+    from: AccountId,
+    id: Id,
+    data: Vec<u8>,
+  ) -> Result<(), PSP34ReceiverError>;
+}
+
+/// The PSP34 Metadata extension.
+#[ink::trait_definition]
+pub trait PSP34Metadata {
+  /// Returns the attribute of `id` for the given `key`.
+  ///
+  /// If `id` is a collection id of the token, it returns attributes for collection.
+  #[ink(message)]
+  fn get_attribute(&self, id: Id, key: Vec<u8>) -> Option<Vec<u8>>;
+}
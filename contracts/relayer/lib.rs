@@ -35,6 +35,9 @@
 //! ## Execute
 //!
 //! The user calls the NFT contract's function through the relayer contract.
+//! The params must be of the number and types of the function: `execute` fails with `BadLength`
+//! before the proof is checked, and with `InvalidParam` before the function is called.
+//! The note is only spent if the NFT contract executed the function without an error.
 
 // This contract inspired by [OpenZKP from patractlabs](https://github.com/patractlabs/OpenZKP).
 
@@ -46,9 +49,11 @@ pub mod constants;
 #[ink::contract]
 pub mod relayer {
     use crate::constants::{IV, SCALAR_FIELD, ZEROS};
+    use erc721::{erc721::Error as Erc721Error, traits::Id};
     use ink_prelude::{string::String, vec, vec::Vec};
     use ink_storage::{traits::SpreadAllocate, Mapping};
     use scale::{Decode, Encode};
+    use verifier::VerifierError;
     // use zkmega_arkworks::{curve::Bn254, groth16};
     use zkp_u256::U256;
     #[ink(event)]
@@ -82,6 +87,7 @@ pub mod relayer {
         ThirdContractExecutionFailed,
         InvalidContractAddress,
         WithdrawFailed,
+        InvalidParam,
     }
 
     // Define the param type of the third contract
@@ -91,6 +97,7 @@ pub mod relayer {
         TokenId(u32),
         AccountId(AccountId),
         String(String),
+        ViewTag(u8),
     }
 
     impl Param {
@@ -108,6 +115,7 @@ pub mod relayer {
         TokenId(u32),
         AccountId(ink_env::AccountId),
         String(String),
+        ViewTag(u8),
     }
 
     // Define the executable functions of the third contract
@@ -119,6 +127,7 @@ pub mod relayer {
         TransferFrom,
         Mint,
         Burn,
+        TransferBatch,
     }
 
     const ROOT_HISTORY_SIZE: u32 = 30; // merkle tree history size
@@ -135,12 +144,14 @@ pub mod relayer {
         pub erc721: AccountId,
         // Stores commitments
         pub commitments: Mapping<String, bool>,
-        // Stores nullifier_hashes
-        pub nullifier_hashes: Mapping<String, bool>,
+        // Stores nullifier_hashes as little-endian field elements, so a note has a single nullifier
+        // whatever the case or leading zeros of its hex string
+        pub nullifier_hashes: Mapping<[u8; 32], bool>,
         // merkle tree level
         pub levels: u32,
         pub filled_subtrees: Mapping<u32, String>,
-        pub roots: Mapping<u32, String>,
+        // Stores roots as little-endian field elements, so every hex string of a root matches it
+        pub roots: Mapping<u32, [u8; 32]>,
         pub current_root_index: u32,
         pub next_index: u32,
     }
@@ -161,14 +172,19 @@ pub mod relayer {
                 self.filled_subtrees
                     .insert(i, &String::from(ZEROS[i as usize]));
             }
-            self.roots
-                .insert(0, &String::from(ZEROS[(levels - 1) as usize]));
+            self.roots.insert(
+                0,
+                &Self::decode_field_input(ZEROS[(levels - 1) as usize]).unwrap(),
+            );
         }
 
         /// Returns if hash is included in nullifier_hashes.
         #[ink(message)]
         pub fn is_hash_nullified(&self, nullifier_hash: String) -> bool {
-          self.nullifier_hashes.contains(nullifier_hash)
+          match Self::decode_field_input(&nullifier_hash) {
+            Ok(nullifier) => self.nullifier_hashes.contains(nullifier),
+            Err(_) => false,
+          }
         }
 
         /// Returns the public keys of the alias.
@@ -247,13 +263,23 @@ pub mod relayer {
             selector: [u8; 4],
             contract_params: Vec<Param>,
         ) -> Result<(), Error> {
-            if self
-                .withdraw(proof, root, nullifier_hash, recipient, relayer, fee, refund)
-                .is_err()
-            {
-                return Err(Error::WithdrawFailed);
+            // Each function takes a fixed number of params, and a batch 5 params per transfer:
+            // to, id, ephemeral_public_key, view_tag, signature
+            let expected_length = match function {
+                NFTFunction::Approve | NFTFunction::Transfer => contract_params.len() == 5,
+                NFTFunction::TransferFrom => contract_params.len() == 6,
+                NFTFunction::Mint => contract_params.len() == 3,
+                NFTFunction::Burn => contract_params.len() == 2,
+                NFTFunction::TransferBatch => contract_params.len() % 5 == 0,
+            };
+            if !expected_length {
+                return Err(Error::BadLength);
             }
 
+            let nullifier = self
+                .check_withdraw(&proof, &root, &nullifier_hash, recipient, relayer, fee, refund)
+                .map_err(|_| Error::WithdrawFailed)?;
+
             if self.erc721 == AccountId::from([0; 32]) {
                 return Err(Error::InvalidContractAddress);
             }
@@ -261,23 +287,32 @@ pub mod relayer {
             let contract = self.erc721;
 
             // Match function of erc721 contract 
-            match function {
+            let result = match function {
                 NFTFunction::Approve | NFTFunction::Transfer => {
-                    let to = contract_params[0].get_value::<AccountId>().unwrap();
-                    let id = contract_params[1].get_value::<u32>().unwrap();
-                    let ephemeral_public_key = contract_params[2].get_value::<String>().unwrap();
-                    let signature = contract_params[3].get_value::<String>().unwrap();
-                    crate::call!(contract, selector, to, id, ephemeral_public_key, signature)
-                        .returns::<()>()
-                        .fire()
-                        .unwrap();
+                    let to = Self::param::<AccountId>(&contract_params, 0)?;
+                    let id = Id::U32(Self::param::<u32>(&contract_params, 1)?);
+                    let ephemeral_public_key = Self::param::<String>(&contract_params, 2)?;
+                    let view_tag = Self::param::<u8>(&contract_params, 3)?;
+                    let signature = Self::param::<String>(&contract_params, 4)?;
+                    crate::call!(
+                        contract,
+                        selector,
+                        to,
+                        id,
+                        ephemeral_public_key,
+                        view_tag,
+                        signature
+                    )
+                    .returns::<Result<(), Erc721Error>>()
+                    .fire()
                 }
                 NFTFunction::TransferFrom => {
-                    let from = contract_params[0].get_value::<String>().unwrap();
-                    let to = contract_params[1].get_value::<AccountId>().unwrap();
-                    let id = contract_params[2].get_value::<u32>().unwrap();
-                    let ephemeral_public_key = contract_params[3].get_value::<String>().unwrap();
-                    let signature = contract_params[4].get_value::<String>().unwrap();
+                    let from = Self::param::<AccountId>(&contract_params, 0)?;
+                    let to = Self::param::<AccountId>(&contract_params, 1)?;
+                    let id = Id::U32(Self::param::<u32>(&contract_params, 2)?);
+                    let ephemeral_public_key = Self::param::<String>(&contract_params, 3)?;
+                    let view_tag = Self::param::<u8>(&contract_params, 4)?;
+                    let signature = Self::param::<String>(&contract_params, 5)?;
                     crate::call!(
                         contract,
                         selector,
@@ -285,31 +320,52 @@ pub mod relayer {
                         to,
                         id,
                         ephemeral_public_key,
+                        view_tag,
                         signature
                     )
-                    .returns::<()>()
+                    .returns::<Result<(), Erc721Error>>()
                     .fire()
-                    .unwrap();
                 }
                 NFTFunction::Mint => {
-                    // owner: AccountId, ephemeral_public_key: String
-                    let owner = contract_params[0].get_value::<AccountId>().unwrap();
-                    let ephemeral_public_key = contract_params[1].get_value::<String>().unwrap();
-                    crate::call!(contract, selector, owner, ephemeral_public_key)
-                        .returns::<()>()
+                    // owner: AccountId, ephemeral_public_key: String, view_tag: u8
+                    let owner = Self::param::<AccountId>(&contract_params, 0)?;
+                    let ephemeral_public_key = Self::param::<String>(&contract_params, 1)?;
+                    let view_tag = Self::param::<u8>(&contract_params, 2)?;
+                    crate::call!(contract, selector, owner, ephemeral_public_key, view_tag)
+                        .returns::<Result<(), Erc721Error>>()
                         .fire()
-                        .unwrap();
                 }
                 NFTFunction::Burn => {
                     //id: TokenId, signature: String
-                    let id = contract_params[0].get_value::<u32>().unwrap();
-                    let signature = contract_params[1].get_value::<String>().unwrap();
+                    let id = Id::U32(Self::param::<u32>(&contract_params, 0)?);
+                    let signature = Self::param::<String>(&contract_params, 1)?;
                     crate::call!(contract, selector, id, signature)
-                        .returns::<()>()
+                        .returns::<Result<(), Erc721Error>>()
+                        .fire()
+                }
+                NFTFunction::TransferBatch => {
+                    // transfers: Vec<(AccountId, Id, String, u8, String)>
+                    let transfers: Vec<(AccountId, Id, String, u8, String)> = contract_params
+                        .chunks(5)
+                        .map(|params| {
+                            Ok((
+                                Self::param::<AccountId>(params, 0)?,
+                                Id::U32(Self::param::<u32>(params, 1)?),
+                                Self::param::<String>(params, 2)?,
+                                Self::param::<u8>(params, 3)?,
+                                Self::param::<String>(params, 4)?,
+                            ))
+                        })
+                        .collect::<Result<_, Error>>()?;
+                    crate::call!(contract, selector, transfers)
+                        .returns::<Result<(), Erc721Error>>()
                         .fire()
-                        .unwrap();
                 }
             };
+            // The note is only spent if the erc721 contract executed the function
+            Self::check_execution(result)?;
+
+            self.spend(nullifier, nullifier_hash, recipient, relayer, fee, refund);
             Ok(())
         }
 
@@ -324,16 +380,40 @@ pub mod relayer {
             fee: u128,
             refund: u128,
         ) -> Result<(), Error> {
-            if !self.is_known_root(root.clone()) {
+            let nullifier =
+                self.check_withdraw(&proof, &root, &nullifier_hash, recipient, relayer, fee, refund)?;
+            self.spend(nullifier, nullifier_hash, recipient, relayer, fee, refund);
+            Ok(())
+        }
+
+        /// Check that the note is not spent yet, and the proof of the withdrawal is verified
+        ///
+        /// Returns the nullifier hash as little-endian field element
+        fn check_withdraw(
+            &self,
+            proof: &str,
+            root: &str,
+            nullifier_hash: &str,
+            recipient: AccountId,
+            relayer: AccountId,
+            fee: u128,
+            refund: u128,
+        ) -> Result<[u8; 32], Error> {
+            if !self.is_known_root(String::from(root)) {
                 return Err(Error::RootNotExist);
             }
-            if self.nullifier_hashes.contains(nullifier_hash.clone()) {
+            let nullifier_input = Self::decode_field_input(nullifier_hash)?;
+            if self.nullifier_hashes.contains(nullifier_input) {
                 return Err(Error::AlreadySpent);
             }
             if self.verifier == AccountId::from([0; 32]) {
                 return Err(Error::InvalidContractAddress);
             }
 
+            // The verifier takes the proof as bytes, and the root and nullifier hash as little-endian bytes
+            let proof_bytes = hex::decode(proof).map_err(|_| Error::InvalidWithdrawProof)?;
+            let root_input = Self::decode_field_input(root)?;
+
             // The selector of function verify() from contract verifier, copied from target/ink/metadata.json after contract verifier compiled
             // selector = 0x1860ff3b
             let selector: [u8; 4] = [0x18, 0x60, 0xff, 0x3b];
@@ -341,23 +421,41 @@ pub mod relayer {
             let verify_result = crate::call!(
                 contract,
                 selector,
-                proof,
-                root,
-                nullifier_hash.clone(),
+                proof_bytes,
+                root_input,
+                nullifier_input,
                 recipient,
                 relayer,
                 fee,
                 refund
             )
-            .returns::<bool>()
+            .returns::<Result<bool, VerifierError>>()
             .fire()
-            .unwrap();
-            if !verify_result {
-                return Err(Error::VerifyFailed);
+            .map_err(|_| Error::VerifyCatchErr)?;
+            match verify_result {
+                Ok(true) => Ok(nullifier_input),
+                Ok(false) => Err(Error::VerifyFailed),
+                // The verifier itself failed, or lacks the withdraw circuit whose inputs are packed
+                // by `verify`, the proof may still be valid
+                Err(VerifierError::PairingFailed)
+                | Err(VerifierError::CircuitNotFound)
+                | Err(VerifierError::BadInputLength) => Err(Error::VerifyCatchErr),
+                Err(_) => Err(Error::InvalidWithdrawProof),
             }
-            
+        }
+
+        /// Nullifier the note, and transfer token to recipient and relayer
+        fn spend(
+            &mut self,
+            nullifier: [u8; 32],
+            nullifier_hash: String,
+            recipient: AccountId,
+            relayer: AccountId,
+            fee: u128,
+            refund: u128,
+        ) {
             // nullifier hash
-            self.nullifier_hashes.insert(nullifier_hash.clone(), &true);
+            self.nullifier_hashes.insert(nullifier, &true);
 
             // transfer token to recipient and relayer
             self.process_transfer(recipient, relayer, fee, refund);
@@ -368,7 +466,36 @@ pub mod relayer {
                 relayer,
                 fee,
             });
-            Ok(())
+        }
+
+        /// Returns the param at `index`, or an error if it is missing or of another type
+        fn param<T: Value>(params: &[Param], index: usize) -> Result<T::Type, Error> {
+            params
+                .get(index)
+                .and_then(Param::get_value::<T>)
+                .ok_or(Error::InvalidParam)
+        }
+
+        /// Returns an error if the call of the erc721 contract failed, or its function returned an error
+        fn check_execution(result: ink_env::Result<Result<(), Erc721Error>>) -> Result<(), Error> {
+            match result {
+                Ok(Ok(())) => Ok(()),
+                _ => Err(Error::ThirdContractExecutionFailed),
+            }
+        }
+
+        /// Decode a big-endian hex field element into little-endian bytes
+        ///
+        /// Leading zeros may be omitted, like in the roots computed by `insert`
+        fn decode_field_input(value: &str) -> Result<[u8; 32], Error> {
+            if value.len() > 64 {
+                return Err(Error::InvalidWithdrawProof);
+            }
+            let value = "0".repeat(64 - value.len()) + value;
+            let mut input = [0u8; 32];
+            hex::decode_to_slice(value, &mut input).map_err(|_| Error::InvalidWithdrawProof)?;
+            input.reverse();
+            Ok(input)
         }
 
         /// Transfer token to relayer and recipient
@@ -392,6 +519,10 @@ pub mod relayer {
         /// Whether the root is present in the root history
         #[ink(message)]
         pub fn is_known_root(&self, root: String) -> bool {
+            let root = match Self::decode_field_input(&root) {
+                Ok(root) => root,
+                Err(_) => return false,
+            };
             for i in (0..self.current_root_index + 1).rev() {
                 if i == 0 {
                     return false;
@@ -458,7 +589,8 @@ pub mod relayer {
             }
             let new_root_index = (self.current_root_index + 1) % ROOT_HISTORY_SIZE;
             self.current_root_index = new_root_index;
-            self.roots.insert(new_root_index, &current_level_hash);
+            let root = Self::decode_field_input(&current_level_hash).unwrap();
+            self.roots.insert(new_root_index, &root);
             self.next_index = next_index + 1;
             Ok(next_index)
         }
@@ -478,6 +610,8 @@ pub mod relayer {
         use ink_lang as ink;
         const ROOT: &str = "1b68d520e3f0594a45d3b1ee6cff14962272b66547a218053beb57b07bf36bc4";
         const COMMITMENT: &str = "5ad3068cfac36d516b1f4844ee0885940c670d5f4cdd9ff7826235476fdde3b";
+        // The root after depositing commitment 3
+        const SHORT_ROOT: &str = "bca59f17d6225fdb559e51338b8a95654385996e947012b0c2972c1c9a2081f";
         const NULLIFIER_HASH: &str =
             "299a100c01c4e16cc745af1091fb77e36621a24b36edec50cb7d1aee8c54304b";
         const PROOF: &str = "881bc81785063689cea604fe6df802ffbad64344db5711e8b40b4ce5f7b001068189bbb27a9c980ba66d1c90d955849ea2eed93356e64819bd9f813c2481d510000a4dc82dbdda164e7a6931f02f7b59abcad786b4a081f0aca38e24beec92293017729df011542417d0bf8a18d93a4973fc78a2b61817aff346dc766c5d6d231bd5d3aa7e083815c9b0a9f3047c20aaa89f34f8b16d7e183d108ece6f92ed871f33bcf82fc1c75ca5319e26ef117261ce02dc3f133a9acfc2ad73d7008690832800cc5e9c949bf0d1a2ccb5b45419b21c749af5d163d10059b6662a1ae7c98ec82ad34d3ac58810f5ae7f27dfcaf0e4bdbbe0f50fd7c396845bf2d76f03363a8c0f00";
@@ -500,6 +634,31 @@ pub mod relayer {
             assert!(relayer.is_known_root(root));
         }
 
+        #[ink::test]
+        fn short_root_works() {
+            let mut relayer = Relayer::new(10, AccountId::from([0; 32]), AccountId::from([0; 32]));
+            let accounts = default_accounts::<DefaultEnvironment>();
+            test::set_caller::<DefaultEnvironment>(accounts.alice);
+            test::set_balance::<DefaultEnvironment>(accounts.alice, 10000000000000);
+            test::set_value_transferred::<DefaultEnvironment>(1000000000000);
+
+            // The hex string of the root omits its leading zero
+            relayer.deposit(String::from("3")).unwrap();
+            assert_eq!(SHORT_ROOT.len(), 63);
+            assert!(relayer.is_known_root(String::from(SHORT_ROOT)));
+            assert!(relayer.is_known_root(String::from("0") + SHORT_ROOT));
+
+            // The verifier takes the same field element either way
+            assert_eq!(
+                Relayer::decode_field_input(SHORT_ROOT),
+                Relayer::decode_field_input(&(String::from("0") + SHORT_ROOT))
+            );
+            assert_eq!(
+                Relayer::decode_field_input(&"0".repeat(65)),
+                Err(Error::InvalidWithdrawProof)
+            );
+        }
+
         #[ink::test]
         fn test_withdrawal() {
             let mut relayer = Relayer::new(10, AccountId::from([0; 32]), AccountId::from([0; 32]));
@@ -543,6 +702,43 @@ pub mod relayer {
             assert_eq!(relayer.is_hash_nullified(nullifier_hash.clone()), false);
         }
 
+        #[ink::test]
+        fn spent_note_fails() {
+            let mut relayer = Relayer::new(10, AccountId::from([0; 32]), AccountId::from([0; 32]));
+            let accounts = default_accounts::<DefaultEnvironment>();
+            test::set_caller::<DefaultEnvironment>(accounts.alice);
+            test::set_balance::<DefaultEnvironment>(accounts.alice, 10000000000000);
+            test::set_value_transferred::<DefaultEnvironment>(1000000000000);
+            relayer.deposit(String::from(COMMITMENT)).unwrap();
+
+            // Spend the note like a verified withdrawal
+            let nullifier = Relayer::decode_field_input(NULLIFIER_HASH).unwrap();
+            relayer.spend(
+                nullifier,
+                String::from(NULLIFIER_HASH),
+                accounts.bob,
+                accounts.charlie,
+                0,
+                0,
+            );
+
+            // The note cannot be withdrawn again by another hex string of its nullifier hash
+            let nullifier_hash = NULLIFIER_HASH.to_uppercase();
+            assert!(relayer.is_hash_nullified(nullifier_hash.clone()));
+            assert_eq!(
+                relayer.withdrawal(
+                    String::from(PROOF),
+                    String::from(ROOT),
+                    nullifier_hash,
+                    accounts.bob,
+                    accounts.charlie,
+                    0,
+                    0,
+                ),
+                Err(Error::AlreadySpent)
+            );
+        }
+
         #[ink::test]
         fn test_execute() {
             let mut relayer = Relayer::new(10, AccountId::from([0; 32]), AccountId::from([0; 32]));
@@ -578,6 +774,7 @@ pub mod relayer {
             let mut params: Vec<Param> = Vec::new();
             params.push(Param::AccountId(owner));
             params.push(Param::String(ephemeral_public_key));
+            params.push(Param::ViewTag(0));
 
             // Payable
             let accounts = default_accounts::<DefaultEnvironment>();
@@ -604,6 +801,68 @@ pub mod relayer {
             assert_eq!(relayer.is_hash_nullified(nullifier_hash.clone()), false);
         }
 
+        #[ink::test]
+        fn bad_params_fail() {
+            let mut relayer = Relayer::new(10, AccountId::from([0; 32]), AccountId::from([0; 32]));
+            let accounts = default_accounts::<DefaultEnvironment>();
+            let params = vec![Param::TokenId(1), Param::String(String::from("signature"))];
+
+            // A burn takes a token id and a signature, which is checked before the proof
+            assert_eq!(
+                relayer.execute(
+                    String::from(PROOF),
+                    String::from(ROOT),
+                    String::from(NULLIFIER_HASH),
+                    accounts.bob,
+                    accounts.charlie,
+                    0,
+                    0,
+                    NFTFunction::Burn,
+                    [0; 4],
+                    params[..1].to_vec()
+                ),
+                Err(Error::BadLength)
+            );
+            assert_eq!(
+                relayer.execute(
+                    String::from(PROOF),
+                    String::from(ROOT),
+                    String::from(NULLIFIER_HASH),
+                    accounts.bob,
+                    accounts.charlie,
+                    0,
+                    0,
+                    NFTFunction::Mint,
+                    [0; 4],
+                    params.clone()
+                ),
+                Err(Error::BadLength)
+            );
+
+            // A param of another type or a missing param is an error rather than a panic
+            assert_eq!(Relayer::param::<u32>(&params, 0), Ok(1));
+            assert_eq!(Relayer::param::<String>(&params, 1), Ok(String::from("signature")));
+            assert_eq!(Relayer::param::<AccountId>(&params, 0), Err(Error::InvalidParam));
+            assert_eq!(Relayer::param::<u8>(&params, 2), Err(Error::InvalidParam));
+        }
+
+        #[ink::test]
+        fn failed_execution_fails() {
+            // erc721 rejects a batch with one bad signature by Err(NotOwner),
+            // which has to be decoded as an error rather than a unit return value
+            let output = Err::<(), Erc721Error>(Erc721Error::NotOwner).encode();
+            let result = <Result<(), Erc721Error>>::decode(&mut &output[..]).unwrap();
+            assert_eq!(
+                Relayer::check_execution(Ok(result)),
+                Err(Error::ThirdContractExecutionFailed)
+            );
+            assert_eq!(
+                Relayer::check_execution(Err(ink_env::Error::CalleeTrapped)),
+                Err(Error::ThirdContractExecutionFailed)
+            );
+            assert_eq!(Relayer::check_execution(Ok(Ok(()))), Ok(()));
+        }
+
         #[ink::test]
         fn mimc_sponge() {
             // let inputs = vec![U256::ZERO.to_hex_string(), U256::ZERO.to_hex_string()];
@@ -747,7 +1006,8 @@ macro_rules! call {
                             .transferred_value(0),
                     )
                     .exec_input(args)
-                    .call_flags(ink_env::CallFlags::default().set_allow_reentry(true))
+                    // The note of `execute` is spent after the call, so the callee must not reenter
+                    .call_flags(ink_env::CallFlags::default())
             }
         };
     }
@@ -4,4 +4,5 @@ set -eu
 
 cargo test --manifest-path relayer/Cargo.toml
 cargo test --manifest-path verifier/Cargo.toml
+cargo test --manifest-path verifier/Cargo.toml --features chain-extension
 cargo test --manifest-path erc721/Cargo.toml
\ No newline at end of file
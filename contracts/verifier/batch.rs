@@ -0,0 +1,138 @@
+//! Batch verification of groth16 proofs.
+//!
+//! The proofs of a circuit are verified by a random linear combination of their pairing
+//! equations, so `n` proofs share one miller loop and one final exponentiation:
+//!
+//! `e(-Σ r_j·alpha, beta) · Π e(r_j·a_j, b_j) · e(-Σ r_j·ic_j, gamma) · e(-Σ r_j·c_j, delta) = 1`
+//!
+//! where `ic_j` is the linear combination of `gamma_abc` with the public inputs of proof `j`.
+//! The 128 bit scalars `r_j` are derived from the hash of the whole batch, so a prover can't
+//! pick invalid proofs which cancel out each other.
+
+use crate::proof::{decode_fq, decode_fr, Engine, Proof};
+use crate::verifier::{VerificationKey, VerifierError};
+use ark_ec::{AffineCurve, ProjectiveCurve};
+use ark_ff::{PrimeField, Zero};
+use core::convert::TryInto;
+use ink_env::hash::Blake2x256;
+use ink_prelude::vec::Vec;
+
+/// A verification key with decoded points.
+pub struct PreparedVerificationKey<E: Engine> {
+    alpha: E::G1Affine,
+    beta: E::G2Affine,
+    gamma: E::G2Affine,
+    delta: E::G2Affine,
+    gamma_abc: Vec<E::G1Affine>,
+}
+
+impl<E: Engine> PreparedVerificationKey<E> {
+    /// Decodes the hex encoded points of a verification key.
+    pub fn decode(verification_key: &VerificationKey) -> Result<Self, VerifierError> {
+        let vk = verification_key
+            .vk
+            .iter()
+            .map(|element| decode_fq_hex::<E>(element))
+            .collect::<Result<Vec<E::Fq>, _>>()?;
+        let gamma_abc = verification_key
+            .gamma_abc
+            .iter()
+            .map(|element| decode_fq_hex::<E>(element))
+            .collect::<Result<Vec<E::Fq>, _>>()?;
+        if vk.len() != 14 || gamma_abc.len() % 2 != 0 {
+            return Err(VerifierError::PairingFailed);
+        }
+
+        let g1 = |elements: &[E::Fq]| E::g1(elements[0], elements[1], false);
+        let g2 = |elements: &[E::Fq]| {
+            E::g2(
+                E::fq2(elements[0], elements[1]),
+                E::fq2(elements[2], elements[3]),
+                false,
+            )
+        };
+        Ok(Self {
+            alpha: g1(&vk[0..2]).map_err(|_| VerifierError::PairingFailed)?,
+            beta: g2(&vk[2..6]).map_err(|_| VerifierError::PairingFailed)?,
+            gamma: g2(&vk[6..10]).map_err(|_| VerifierError::PairingFailed)?,
+            delta: g2(&vk[10..14]).map_err(|_| VerifierError::PairingFailed)?,
+            gamma_abc: gamma_abc
+                .chunks(2)
+                .map(g1)
+                .collect::<Result<_, _>>()
+                .map_err(|_| VerifierError::PairingFailed)?,
+        })
+    }
+}
+
+/// Decodes the encoded proofs and public inputs, and verifies them against the verification key.
+///
+/// Fails if a proof has the wrong number of public inputs.
+pub fn decode_and_verify<E: Engine>(
+    verification_key: &VerificationKey,
+    batch: &[(Vec<u8>, Vec<[u8; 32]>)],
+    seed: &[u8; 32],
+) -> Result<bool, VerifierError> {
+    let proofs = batch
+        .iter()
+        .map(|(proof, public_inputs)| {
+            let public_inputs = public_inputs
+                .iter()
+                .map(decode_fr::<E>)
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok((Proof::<E>::decode(proof)?, public_inputs))
+        })
+        .collect::<Result<Vec<_>, VerifierError>>()?;
+    verify_batch(verification_key, &proofs, seed)
+}
+
+/// Verifies the proofs with their public inputs against the verification key.
+///
+/// Returns `true` if every proof verifies.
+pub fn verify_batch<E: Engine>(
+    verification_key: &VerificationKey,
+    batch: &[(Proof<E>, Vec<E::Fr>)],
+    seed: &[u8; 32],
+) -> Result<bool, VerifierError> {
+    let vk = PreparedVerificationKey::<E>::decode(verification_key)?;
+
+    let mut pairs: Vec<(E::G1Affine, E::G2Affine)> = Vec::with_capacity(batch.len() + 3);
+    let mut r_sum = E::Fr::zero();
+    let mut ic_sum = E::G1Projective::zero();
+    let mut c_sum = E::G1Projective::zero();
+    for (index, (proof, public_inputs)) in batch.iter().enumerate() {
+        if public_inputs.len() + 1 != vk.gamma_abc.len() {
+            return Err(VerifierError::BadInputLength);
+        }
+        let r = random_scalar::<E::Fr>(seed, index as u32);
+
+        let mut ic = vk.gamma_abc[0].into_projective();
+        for (input, point) in public_inputs.iter().zip(&vk.gamma_abc[1..]) {
+            ic += &point.mul(input.into_repr());
+        }
+        ic_sum += &ic.mul(r.into_repr());
+        c_sum += &proof.c.mul(r.into_repr());
+        r_sum += &r;
+        pairs.push((proof.a.mul(r.into_repr()).into_affine(), proof.b));
+    }
+    pairs.push(((-vk.alpha.mul(r_sum.into_repr())).into_affine(), vk.beta));
+    pairs.push(((-ic_sum).into_affine(), vk.gamma));
+    pairs.push(((-c_sum).into_affine(), vk.delta));
+
+    E::pairing_check(&pairs)
+}
+
+/// Derives the scalar of the proof at `index` from the seed.
+pub fn random_scalar<F: PrimeField>(seed: &[u8; 32], index: u32) -> F {
+    let mut hash = [0u8; 32];
+    ink_env::hash_bytes::<Blake2x256>(&[&seed[..], &index.to_le_bytes()[..]].concat(), &mut hash);
+    F::from(u128::from_le_bytes(hash[..16].try_into().unwrap()))
+}
+
+/// Decodes a big-endian hex coordinate of the verification key.
+pub fn decode_fq_hex<E: Engine>(element: &str) -> Result<E::Fq, VerifierError> {
+    let mut bytes =
+        hex::decode(element.trim_start_matches("0x")).map_err(|_| VerifierError::PairingFailed)?;
+    bytes.reverse();
+    decode_fq::<E>(&bytes).map_err(|_| VerifierError::PairingFailed)
+}
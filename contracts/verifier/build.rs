@@ -0,0 +1,173 @@
+//! Generates the verification key constants of the withdraw circuit from the ZoKrates
+//! `verification.key` in `build/`, or in the file at `$VERIFICATION_KEY`.
+//!
+//! The coordinates are checked to be elements of the BN254 base field, and the points to be
+//! on the curve, and emitted as 32 byte little-endian field elements.
+
+use num_bigint::BigUint;
+use serde_json::Value;
+use std::{env, fmt::Write, fs, path::PathBuf};
+
+/// The modulus of the BN254 base field.
+const MODULUS: &str =
+    "21888242871839275222246405745257275088696311157297823662689037894645226208583";
+
+/// The coefficient `b` of the G1 curve `y^2 = x^3 + b`.
+const G1_B: u32 = 3;
+
+/// The coefficient `b / (9 + u)` of the G2 twist `y^2 = x^3 + b / (9 + u)`, as `[c0, c1]`.
+const G2_B: [&str; 2] = [
+    "19485874751759354771024239261021720505790618469301721065564631296452457478373",
+    "266929791119991161246907387137283842545076965332900288569378510910307636690",
+];
+
+fn main() {
+    let path = match env::var_os("VERIFICATION_KEY") {
+        Some(path) => PathBuf::from(path),
+        None => PathBuf::from(env::var_os("CARGO_MANIFEST_DIR").unwrap())
+            .join("../../build/verification.key"),
+    };
+    println!("cargo:rerun-if-env-changed=VERIFICATION_KEY");
+    println!("cargo:rerun-if-changed={}", path.display());
+
+    let json = fs::read_to_string(&path)
+        .unwrap_or_else(|error| panic!("cannot read {}: {}", path.display(), error));
+    let key: Value = serde_json::from_str(&json)
+        .unwrap_or_else(|error| panic!("cannot parse {}: {}", path.display(), error));
+    if key["scheme"] != "g16" || key["curve"] != "bn128" {
+        panic!(
+            "{} is not a groth16 verification key over bn128",
+            path.display()
+        );
+    }
+
+    let field = Field::bn254();
+    // VK = [alpha beta gamma delta]
+    let mut vk = field.g1(&key["alpha"]);
+    for point in ["beta", "gamma", "delta"].iter() {
+        vk.extend(field.g2(&key[*point]));
+    }
+    let gamma_abc: Vec<BigUint> = key["gamma_abc"]
+        .as_array()
+        .expect("gamma_abc is not an array of G1 points")
+        .iter()
+        .flat_map(|point| field.g1(point))
+        .collect();
+
+    let mut constants = format!(
+        "// Generated by build.rs from {}, do not edit.\n\n",
+        path.display()
+    );
+    write_array(&mut constants, "VK", &vk);
+    write_array(&mut constants, "VK_GAMMA_ABC", &gamma_abc);
+    let out = PathBuf::from(env::var_os("OUT_DIR").unwrap()).join("verification_key.rs");
+    fs::write(out, constants).unwrap();
+}
+
+/// An element `c0 + c1 * u` of the quadratic extension hosting G2.
+type Fq2 = (BigUint, BigUint);
+
+/// The arithmetic of the BN254 base field and its quadratic extension, where `u^2 = -1`.
+struct Field {
+    modulus: BigUint,
+}
+
+impl Field {
+    fn bn254() -> Self {
+        Self {
+            modulus: MODULUS.parse().unwrap(),
+        }
+    }
+
+    /// Returns the coordinates of a G1 point, `[x, y]`, if it is on the curve.
+    fn g1(&self, point: &Value) -> Vec<BigUint> {
+        let (x, y) = match point.as_array().map(Vec::as_slice) {
+            Some([x, y]) => (self.element(x), self.element(y)),
+            _ => panic!("{} is not a G1 point", point),
+        };
+        let rhs = self.add(&self.mul(&self.mul(&x, &x), &x), &BigUint::from(G1_B));
+        if self.mul(&y, &y) != rhs {
+            panic!("{} is not on the G1 curve", point);
+        }
+        vec![x, y]
+    }
+
+    /// Returns the coordinates of a G2 point, `[x0, x1, y0, y1]` of `[[x0, x1], [y0, y1]]`,
+    /// if it is on the twist.
+    fn g2(&self, point: &Value) -> Vec<BigUint> {
+        let (x, y) = match point.as_array().map(Vec::as_slice) {
+            Some([x, y]) => (self.element2(x), self.element2(y)),
+            _ => panic!("{} is not a G2 point", point),
+        };
+        let b = (G2_B[0].parse().unwrap(), G2_B[1].parse().unwrap());
+        let rhs = self.add2(&self.mul2(&self.mul2(&x, &x), &x), &b);
+        if self.mul2(&y, &y) != rhs {
+            panic!("{} is not on the G2 twist", point);
+        }
+        vec![x.0, x.1, y.0, y.1]
+    }
+
+    /// Returns an element of the quadratic extension, `[c0, c1]`.
+    fn element2(&self, element: &Value) -> Fq2 {
+        match element.as_array().map(Vec::as_slice) {
+            Some([c0, c1]) => (self.element(c0), self.element(c1)),
+            _ => panic!("{} is not an element of the quadratic extension", element),
+        }
+    }
+
+    /// Returns a field element, which is a big-endian hex string of 32 bytes less than the
+    /// modulus.
+    fn element(&self, element: &Value) -> BigUint {
+        let value = match element.as_str() {
+            Some(hex) if hex.len() == 66 && hex.starts_with("0x") => {
+                BigUint::parse_bytes(hex[2..].as_bytes(), 16)
+            }
+            _ => None,
+        };
+        match value {
+            Some(value) if value < self.modulus => value,
+            _ => panic!("{} is not a field element", element),
+        }
+    }
+
+    fn add(&self, a: &BigUint, b: &BigUint) -> BigUint {
+        (a + b) % &self.modulus
+    }
+
+    fn sub(&self, a: &BigUint, b: &BigUint) -> BigUint {
+        (a + &self.modulus - b) % &self.modulus
+    }
+
+    fn mul(&self, a: &BigUint, b: &BigUint) -> BigUint {
+        a * b % &self.modulus
+    }
+
+    fn add2(&self, a: &Fq2, b: &Fq2) -> Fq2 {
+        (self.add(&a.0, &b.0), self.add(&a.1, &b.1))
+    }
+
+    fn mul2(&self, a: &Fq2, b: &Fq2) -> Fq2 {
+        (
+            self.sub(&self.mul(&a.0, &b.0), &self.mul(&a.1, &b.1)),
+            self.add(&self.mul(&a.0, &b.1), &self.mul(&a.1, &b.0)),
+        )
+    }
+}
+
+/// Writes the field elements as 32 byte little-endian arrays.
+fn write_array(constants: &mut String, name: &str, elements: &[BigUint]) {
+    writeln!(
+        constants,
+        "pub const {}: [[u8; 32]; {}] = [",
+        name,
+        elements.len()
+    )
+    .unwrap();
+    for element in elements {
+        let mut bytes = element.to_bytes_le();
+        bytes.resize(32, 0);
+        let bytes: Vec<String> = bytes.iter().map(|byte| format!("{:#04x}", byte)).collect();
+        writeln!(constants, "    [{}],", bytes.join(", ")).unwrap();
+    }
+    constants.push_str("];\n\n");
+}
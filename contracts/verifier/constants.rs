@@ -1,38 +1,14 @@
-// VK = [alpha beta gamma delta]
-pub static VK: [&str; 14] = [
-    "0x1c5ecfdfd8856b51f73199d3777cfb87de3f789e28d562b018e19717cf55622c",
-    "0x1ca644679bb6129196c993b5f508bb4ae3e188a407677a00baec2d2c48f5c69a",
-    "0x280c3551a2395189e6d484dde8b7a0dd06129440debf20769dfe6b60f41ac674",
-    "0x1be4b20cbaa93b17b0d7b9d1f226c3c6ec0c543a8f81c1764e73b8719009bba7",
-    "0x114cec71fc99853635b76df5c70977da724247e4e3ca5eee0cca0f9f9c91fbac",
-    "0x17159390fc02daed1453e7d123239e4338a3f7f1fa1c9afc1b06053f9bc18b29",
-    "0x2b3069a41e3976d3e6895ec6daed43d5fd5a44f4d820987521b978cde482d938",
-    "0x24c15cc06ae17af1ed025066ee747400dc30f3a4a2080d904f36eb82354bbca0",
-    "0x25ebaa9a91bb4c65d0420c8bb3e1d65f51aa4993ebd792510b28ab2a5fa8fb5d",
-    "0x1fa6cecf31027b95dd50125d995f7acb4ce9d5b2b0332c0d7044e293bf5a6c7d",
-    "0x24ad79ac1f86432e7e8e6a766fff76d00a870c86c72205fac910f5ae35b4d052",
-    "0x300065f35be04208146c492b5a181c6b1c933c8869d8f42656e31f55a291361a",
-    "0x05feee6c3744b6c1bfe989e13d0455ff799267e493eaf12b7161556ca9450122",
-    "0x0ed155bf607511c92871c5a5371edcb84d04e0f18bb91a1b21113018561403d9",
-];
+// VK = [alpha beta gamma delta], and VK_GAMMA_ABC, as little-endian field elements, generated
+// by build.rs from the `verification.key` of the withdraw circuit.
+include!(concat!(env!("OUT_DIR"), "/verification_key.rs"));
 
-pub static VK_GAMMA_ABC: [&str; 18] = [
-    "0x28b5c9ec16185a6a3d7bfb69ee53748e2843de3c212f16b216f823f0bed4e73a",
-    "0x18415c6103cfffa562285c68faeaa9c5073444468142b4e3ea34e7f4a716f265",
-    "0x263230fc228119de2ebef0713a22279a10fe9768bd5156d3909f01ed13814d46",
-    "0x099e3c39b50d8db99964a1bc649e5a74f4f934ffc05bae7cd67bd445564e7fa6",
-    "0x23800c9c9afc82739f45b3690d4818774223b27b2ebf51c83b0e41b75ef0d91e",
-    "0x0bd8481f571671fc0c0c01f8d8276bb55a3ace9abbad250d5280de0ccbcf043b",
-    "0x2a7fd7718fff03e7ea579671e46072a44f4b6bcc0ddaf0a7759d8daf9785ef8f",
-    "0x176efff64f76488cde4aee9686ccdd6707129253f798602c29d8db60a4e9450a",
-    "0x2a992e9d6c6f65578eff9b327b98f2cd55c00db12f846fe4123b0e7b2270a63a",
-    "0x02cd2bf65c6cb1f478cf770aa141232ab9a6212b3aec52467f0ebac99d00bedf",
-    "0x2f205d70a95e54fa9be79e3384458ad3feaa46743d0838a01dfe0155be10b601",
-    "0x00df00966cb903cbebb775587b17d95e7d36382ba99125c742a9e268a603b43d",
-    "0x20d5d66df5056945f480b74c3913ef4bfc6f8fed618709cc275824c065d958f2",
-    "0x106994320e8a4564235c86a1e7c794d37fb9e01442fa1884189550cc5cfe1882",
-    "0x1a64bfb4980924e5c1dfa0d607bcc8a37a923d12f68bb156f81bc7fa132660a6",
-    "0x177c428c9562580ab6cc9b3422763e85a53da11c41fe3370102f6cacc673cb49",
-    "0x262925a64e93ffa73acce9cb8ce005e4452a07b02926db2b5b420211bd750109",
-    "0x0d9c03b589482500b70b186a5fb60699c89f51e1b2ef6bc98ab807f3bf554bc5",
-];
+/// The number of public inputs of the withdraw circuit: root, nullifier hash,
+/// recipient and relayer (two inputs each), fee and refund.
+pub const WITHDRAW_NUM_PUBLIC_INPUTS: u32 = 8;
+
+// Fails to compile if the verification key does not match the public inputs of the withdraw
+// circuit, with 2 gamma_abc elements per public input plus one point.
+const _: () = assert!(
+    VK_GAMMA_ABC.len() == 2 * (WITHDRAW_NUM_PUBLIC_INPUTS as usize + 1),
+    "the number of gamma_abc points of verification.key does not match the withdraw circuit"
+);
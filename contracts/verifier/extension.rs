@@ -0,0 +1,85 @@
+//! The chain extension of a runtime which computes BN254 pairings, MiMC and Poseidon natively.
+//!
+//! With the `chain-extension` feature, the verifier runs in `ZkEnvironment`, and checks the
+//! pairings of BN254 proofs by the chain extension rather than in Wasm. Without the feature, the
+//! verifier runs in the default environment, and checks every pairing in Wasm.
+//!
+//! Points are encoded as in proofs, see `proof`, and field elements in 32 little-endian bytes.
+//! The runtime returns the status code 0 on success and 1 for malformed input, and any other code
+//! fails the call rather than the contract.
+
+use crate::verifier::VerifierError;
+use ark_bn254::{G1Affine, G2Affine};
+use ark_ff::to_bytes;
+use ink_env::{DefaultEnvironment, Environment};
+use ink_lang as ink;
+use ink_lang::ChainExtensionInstance;
+use ink_prelude::vec::Vec;
+
+/// The functions of the chain extension.
+#[ink::chain_extension]
+pub trait ZkExtension {
+    type ErrorCode = ZkExtensionError;
+
+    /// Returns whether the product of the pairings of the G1 and G2 points is one.
+    #[ink(extension = 1001, returns_result = false)]
+    fn bn254_pairing_check(pairs: Vec<(Vec<u8>, Vec<u8>)>) -> bool;
+
+    /// Returns the MiMC sponge hash of the field elements, with 220 rounds and key 0 as in the
+    /// withdraw circuit.
+    #[ink(extension = 1002, returns_result = false)]
+    fn mimc_sponge(inputs: Vec<[u8; 32]>) -> [u8; 32];
+
+    /// Returns the Poseidon hash of the field elements, with the parameters of circomlib.
+    #[ink(extension = 1003, returns_result = false)]
+    fn poseidon(inputs: Vec<[u8; 32]>) -> [u8; 32];
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub enum ZkExtensionError {
+    /// The points or field elements are malformed.
+    InvalidInput,
+    /// The runtime returned a status code which the contract doesn't know.
+    UnknownStatusCode,
+}
+
+impl ink_env::chain_extension::FromStatusCode for ZkExtensionError {
+    fn from_status_code(status_code: u32) -> Result<(), Self> {
+        match status_code {
+            0 => Ok(()),
+            1 => Err(Self::InvalidInput),
+            _ => Err(Self::UnknownStatusCode),
+        }
+    }
+}
+
+/// The default environment with the chain extension.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub enum ZkEnvironment {}
+
+impl Environment for ZkEnvironment {
+    const MAX_EVENT_TOPICS: usize = <DefaultEnvironment as Environment>::MAX_EVENT_TOPICS;
+
+    type AccountId = <DefaultEnvironment as Environment>::AccountId;
+    type Balance = <DefaultEnvironment as Environment>::Balance;
+    type Hash = <DefaultEnvironment as Environment>::Hash;
+    type BlockNumber = <DefaultEnvironment as Environment>::BlockNumber;
+    type Timestamp = <DefaultEnvironment as Environment>::Timestamp;
+
+    type ChainExtension = ZkExtension;
+}
+
+/// Returns whether the product of the pairings of the points is one, computed by the runtime.
+///
+/// A failure of the chain extension is reported as `PairingFailed`.
+pub fn bn254_pairing_check(pairs: &[(G1Affine, G2Affine)]) -> Result<bool, VerifierError> {
+    let pairs = pairs
+        .iter()
+        .map(|(g1, g2)| (to_bytes![g1].unwrap(), to_bytes![g2].unwrap()))
+        .collect();
+    <ZkExtension as ChainExtensionInstance>::instantiate()
+        .bn254_pairing_check(pairs)
+        .map_err(|_| VerifierError::PairingFailed)
+}
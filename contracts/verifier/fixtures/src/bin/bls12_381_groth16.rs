@@ -0,0 +1,111 @@
+//! Generates `BLS_VK`, `BLS_VK_GAMMA_ABC`, `BLS_PROOFS` and `BLS_SCALAR_MODULUS` of the verifier
+//! tests: a groth16 setup over BLS12-381, from the rng seeded with 381, of a circuit proving
+//! `x * y = z` and `x + y = w` for the public inputs `z` and `w`, and proofs of it.
+
+use ark_bls12_381::{Bls12_381, Fq, Fr, G1Affine, G2Affine};
+use ark_ff::{BigInteger, Field, PrimeField};
+use ark_groth16::{create_random_proof, generate_random_parameters, prepare_verifying_key};
+use ark_relations::{
+    lc,
+    r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError, Variable},
+};
+use ark_std::rand::{rngs::StdRng, SeedableRng};
+
+/// Proves `x * y = z` and `x + y = w`.
+#[derive(Clone)]
+struct Circuit {
+    x: Fr,
+    y: Fr,
+}
+
+impl ConstraintSynthesizer<Fr> for Circuit {
+    fn generate_constraints(self, cs: ConstraintSystemRef<Fr>) -> Result<(), SynthesisError> {
+        let z = cs.new_input_variable(|| Ok(self.x * self.y))?;
+        let w = cs.new_input_variable(|| Ok(self.x + self.y))?;
+        let x = cs.new_witness_variable(|| Ok(self.x))?;
+        let y = cs.new_witness_variable(|| Ok(self.y))?;
+        cs.enforce_constraint(lc!() + x, lc!() + y, lc!() + z)?;
+        cs.enforce_constraint(lc!() + x + y, lc!() + Variable::One, lc!() + w)?;
+        Ok(())
+    }
+}
+
+/// Returns the big-endian hex encoding of a coordinate, as in a verification key.
+fn hex_coordinate(element: &Fq) -> String {
+    format!("0x{}", hex::encode(element.into_repr().to_bytes_be()))
+}
+
+/// Returns the little-endian encoding of a coordinate, as in a proof.
+fn le(element: &Fq) -> Vec<u8> {
+    element.into_repr().to_bytes_le()
+}
+
+fn encode_g1(point: &G1Affine) -> Vec<u8> {
+    [le(&point.x), le(&point.y), vec![point.infinity as u8]].concat()
+}
+
+fn encode_g2(point: &G2Affine) -> Vec<u8> {
+    [
+        le(&point.x.c0),
+        le(&point.x.c1),
+        le(&point.y.c0),
+        le(&point.y.c1),
+        vec![point.infinity as u8],
+    ]
+    .concat()
+}
+
+fn main() {
+    let mut rng = StdRng::seed_from_u64(381);
+    let circuit = Circuit {
+        x: Fr::from(3u64),
+        y: Fr::from(5u64),
+    };
+    let params = generate_random_parameters::<Bls12_381, _, _>(circuit, &mut rng).unwrap();
+    let vk = &params.vk;
+
+    // VK = [alpha beta gamma delta]
+    let mut elements = vec![
+        hex_coordinate(&vk.alpha_g1.x),
+        hex_coordinate(&vk.alpha_g1.y),
+    ];
+    for point in [&vk.beta_g2, &vk.gamma_g2, &vk.delta_g2].iter() {
+        elements.extend(
+            [point.x.c0, point.x.c1, point.y.c0, point.y.c1]
+                .iter()
+                .map(hex_coordinate),
+        );
+    }
+    println!("BLS_VK {:?}", elements);
+    let gamma_abc: Vec<String> = vk
+        .gamma_abc_g1
+        .iter()
+        .flat_map(|point| vec![hex_coordinate(&point.x), hex_coordinate(&point.y)])
+        .collect();
+    println!("BLS_VK_GAMMA_ABC {:?}", gamma_abc);
+
+    let pvk = prepare_verifying_key(vk);
+    for &(x, y) in [(3u64, 5u64), (7, 11)].iter() {
+        let circuit = Circuit {
+            x: Fr::from(x),
+            y: Fr::from(y),
+        };
+        let proof = create_random_proof(circuit, &params, &mut rng).unwrap();
+        let public_inputs = [Fr::from(x * y), Fr::from(x + y)];
+        assert!(ark_groth16::verify_proof(&pvk, &proof, &public_inputs).unwrap());
+        let encoded = [
+            encode_g1(&proof.a),
+            encode_g2(&proof.b),
+            encode_g1(&proof.c),
+        ]
+        .concat();
+        println!("BLS_PROOF {} {} {}", hex::encode(encoded), x * y, x + y);
+    }
+
+    let modulus: Vec<u8> = Fr::characteristic()
+        .iter()
+        .rev()
+        .flat_map(|limb| limb.to_be_bytes().to_vec())
+        .collect();
+    println!("BLS_SCALAR_MODULUS {}", hex::encode(modulus));
+}
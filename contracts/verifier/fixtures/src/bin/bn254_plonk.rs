@@ -0,0 +1,458 @@
+//! Generates `PLONK_VK`, `PLONK_PROOFS`, `PLONK_VK_CUBIC` and `PLONK_PROOF_CUBIC` of the verifier
+//! tests, in the encoding of `plonk.rs`.
+//!
+//! The setup has the 32 powers `[x^i]` in G1, and `[x]` in G2, of a secret `x` drawn from the rng
+//! seeded with 2022, which is then used for the blinding factors of the proofs. The circuits are
+//! `x * y = z` and `x + y = w` in 8 gates for the public inputs `z` and `w`, and
+//! `x^3 + x + 5 = out` in 4 gates for the public input `out`, with the coset shifts 2 and 3.
+
+use ark_bn254::{Fq, Fr, G1Affine, G1Projective, G2Affine};
+use ark_ec::{AffineCurve, ProjectiveCurve};
+use ark_ff::{BigInteger, Field, One, PrimeField, ToBytes, UniformRand, Zero};
+use ark_poly::{
+    univariate::DensePolynomial, EvaluationDomain, Polynomial, Radix2EvaluationDomain, UVPolynomial,
+};
+use ark_std::rand::{rngs::StdRng, SeedableRng};
+use blake2::{digest::consts::U32, Blake2b, Digest};
+
+type Poly = DensePolynomial<Fr>;
+
+/// The number of powers of the setup, which bounds the degree of the committed polynomials.
+const SRS_SIZE: usize = 32;
+
+/// A wire which is not connected to any other.
+const FREE: usize = usize::MAX;
+
+/// The powers `[x^i]` in G1 and `[x]` in G2 of the setup.
+struct Srs {
+    powers: Vec<G1Affine>,
+    x: G2Affine,
+}
+
+impl Srs {
+    fn new(rng: &mut StdRng) -> Self {
+        let x = Fr::rand(rng);
+        let g1 = G1Affine::prime_subgroup_generator();
+        let mut powers = Vec::with_capacity(SRS_SIZE);
+        let mut power = Fr::one();
+        for _ in 0..SRS_SIZE {
+            powers.push(g1.mul(power.into_repr()).into_affine());
+            power *= x;
+        }
+        let x = G2Affine::prime_subgroup_generator()
+            .mul(x.into_repr())
+            .into_affine();
+        Self { powers, x }
+    }
+
+    fn commit(&self, polynomial: &Poly) -> G1Affine {
+        assert!(polynomial.coeffs.len() <= self.powers.len());
+        let mut commitment = G1Projective::zero();
+        for (coefficient, power) in polynomial.coeffs.iter().zip(&self.powers) {
+            commitment += power.mul(coefficient.into_repr());
+        }
+        commitment.into_affine()
+    }
+}
+
+/// The Fiat-Shamir transcript of `plonk.rs`: a challenge is the Blake2x256 hash of the
+/// appended elements, which then replaces them.
+struct Transcript {
+    bytes: Vec<u8>,
+}
+
+impl Transcript {
+    fn append<T: ToBytes>(&mut self, element: &T) {
+        element.write(&mut self.bytes).unwrap();
+    }
+
+    fn challenge(&mut self) -> Fr {
+        let hash = Blake2b::<U32>::digest(&self.bytes);
+        self.bytes = hash.to_vec();
+        Fr::from_le_bytes_mod_order(&hash)
+    }
+}
+
+/// A gate `q_m * a * b + q_l * a + q_r * b + q_o * c + q_c = 0` on the variables of its wires.
+struct Gate {
+    /// `[q_m, q_l, q_r, q_o, q_c]`
+    selectors: [i64; 5],
+    /// The variables of `[a, b, c]`, or `FREE`.
+    wires: [usize; 3],
+}
+
+/// A circuit of `n` rows, whose first `public` gates take the public inputs on their `a` wire.
+struct Circuit {
+    n: usize,
+    public: usize,
+    values: Vec<Fr>,
+    gates: Vec<Gate>,
+}
+
+fn poly(coeffs: Vec<Fr>) -> Poly {
+    Poly::from_coefficients_vec(coeffs)
+}
+
+fn constant(value: Fr) -> Poly {
+    poly(vec![value])
+}
+
+/// Returns `a * X + b`.
+fn linear(a: Fr, b: Fr) -> Poly {
+    poly(vec![b, a])
+}
+
+fn scale(polynomial: &Poly, factor: Fr) -> Poly {
+    poly(polynomial.coeffs.iter().map(|c| *c * factor).collect())
+}
+
+/// Returns `p(w * X)`.
+fn shift(polynomial: &Poly, w: Fr) -> Poly {
+    let mut power = Fr::one();
+    poly(
+        polynomial
+            .coeffs
+            .iter()
+            .map(|c| {
+                let coefficient = *c * power;
+                power *= w;
+                coefficient
+            })
+            .collect(),
+    )
+}
+
+/// Returns `p / (X - z)`, which must have no remainder.
+fn divide_linear(polynomial: &Poly, z: Fr) -> Poly {
+    let n = polynomial.coeffs.len();
+    let mut quotient = vec![Fr::zero(); n.saturating_sub(1)];
+    let mut carry = Fr::zero();
+    for i in (0..n).rev() {
+        let coefficient = polynomial.coeffs[i] + carry;
+        if i > 0 {
+            quotient[i - 1] = coefficient;
+            carry = coefficient * z;
+        } else {
+            assert!(coefficient.is_zero(), "p(z) is not zero");
+        }
+    }
+    poly(quotient)
+}
+
+fn field(value: i64) -> Fr {
+    if value >= 0 {
+        Fr::from(value as u64)
+    } else {
+        -Fr::from(value.unsigned_abs())
+    }
+}
+
+fn hex_fq(element: &Fq) -> String {
+    format!("0x{}", hex::encode(element.into_repr().to_bytes_be()))
+}
+
+fn hex_fr(element: &Fr) -> String {
+    format!("0x{}", hex::encode(element.into_repr().to_bytes_be()))
+}
+
+/// Returns the verification key of the circuit and a proof of its values.
+fn prove(srs: &Srs, circuit: &Circuit, k1: Fr, k2: Fr, rng: &mut StdRng) -> (Vec<String>, Vec<u8>) {
+    let n = circuit.n;
+    let domain = Radix2EvaluationDomain::<Fr>::new(n).unwrap();
+    assert_eq!(domain.size(), n);
+    let omega = domain.group_gen;
+    let interpolate = |evaluations: &Vec<Fr>| poly(domain.ifft(evaluations));
+
+    // Pads the gates to `n` rows, and connects the free wires to fresh variables.
+    let mut selectors = vec![vec![Fr::zero(); n]; 5];
+    let mut wires = vec![vec![FREE; n]; 3];
+    let mut values = circuit.values.clone();
+    for (row, gate) in circuit.gates.iter().enumerate() {
+        for (selector, value) in selectors.iter_mut().zip(&gate.selectors) {
+            selector[row] = field(*value);
+        }
+        for (wire, variable) in wires.iter_mut().zip(&gate.wires) {
+            wire[row] = *variable;
+        }
+    }
+    for wire in wires.iter_mut() {
+        for variable in wire.iter_mut().filter(|variable| **variable == FREE) {
+            values.push(Fr::zero());
+            *variable = values.len() - 1;
+        }
+    }
+
+    // The permutation maps each wire to the next wire of the same variable, labelled by
+    // `k_j * omega^row`.
+    let shifts = [Fr::one(), k1, k2];
+    let elements: Vec<Fr> = domain.elements().collect();
+    let label = |j: usize, row: usize| shifts[j] * elements[row];
+    let mut positions = vec![vec![]; values.len()];
+    for j in 0..3 {
+        for row in 0..n {
+            positions[wires[j][row]].push((j, row));
+        }
+    }
+    let mut sigma = vec![vec![Fr::zero(); n]; 3];
+    for cycle in &positions {
+        for (k, &(j, row)) in cycle.iter().enumerate() {
+            let (next_j, next_row) = cycle[(k + 1) % cycle.len()];
+            sigma[j][row] = label(next_j, next_row);
+        }
+    }
+
+    let q: Vec<Poly> = selectors.iter().map(interpolate).collect();
+    let s: Vec<Poly> = sigma.iter().map(interpolate).collect();
+    let commitments: Vec<G1Affine> = q.iter().chain(&s).map(|p| srs.commit(p)).collect();
+    let mut vk: Vec<String> = commitments
+        .iter()
+        .flat_map(|point| match point.infinity {
+            true => vec![hex_fq(&Fq::zero()), hex_fq(&Fq::zero())],
+            false => vec![hex_fq(&point.x), hex_fq(&point.y)],
+        })
+        .collect();
+    vk.extend(
+        [srs.x.x.c0, srs.x.x.c1, srs.x.y.c0, srs.x.y.c1]
+            .iter()
+            .map(hex_fq),
+    );
+    vk.extend([Fr::from(n as u64), k1, k2].iter().map(hex_fr));
+
+    let public: Vec<Fr> = (0..circuit.public).map(|i| values[wires[0][i]]).collect();
+    let mut pi_evaluations = vec![Fr::zero(); n];
+    for (i, input) in public.iter().enumerate() {
+        pi_evaluations[i] = -*input;
+    }
+    let pi = interpolate(&pi_evaluations);
+    let wire_evaluations: Vec<Vec<Fr>> = (0..3)
+        .map(|j| (0..n).map(|row| values[wires[j][row]]).collect())
+        .collect();
+    for row in 0..n {
+        let [a, b, c] = [0, 1, 2].map(|j| wire_evaluations[j][row]);
+        let gate = selectors[0][row] * a * b
+            + selectors[1][row] * a
+            + selectors[2][row] * b
+            + selectors[3][row] * c
+            + selectors[4][row]
+            + pi_evaluations[row];
+        assert!(gate.is_zero(), "gate {} is not satisfied", row);
+    }
+
+    let mut transcript = Transcript { bytes: vec![] };
+    for commitment in &commitments {
+        transcript.append(commitment);
+    }
+    transcript.append(&Fr::from(n as u64));
+    transcript.append(&k1);
+    transcript.append(&k2);
+    for input in &public {
+        transcript.append(input);
+    }
+
+    // Round 1: the blinded wire polynomials.
+    let mut vanishing = vec![Fr::zero(); n + 1];
+    vanishing[0] = -Fr::one();
+    vanishing[n] = Fr::one();
+    let vanishing = poly(vanishing);
+    let wire_polys: Vec<Poly> = wire_evaluations
+        .iter()
+        .map(|evaluations| {
+            let blinding = linear(Fr::rand(rng), Fr::rand(rng));
+            &(&vanishing * &blinding) + &interpolate(evaluations)
+        })
+        .collect();
+    let (a, b, c) = (&wire_polys[0], &wire_polys[1], &wire_polys[2]);
+    let (ca, cb, cc) = (srs.commit(a), srs.commit(b), srs.commit(c));
+    transcript.append(&ca);
+    transcript.append(&cb);
+    transcript.append(&cc);
+    let beta = transcript.challenge();
+    let gamma = transcript.challenge();
+
+    // Round 2: the permutation polynomial.
+    let mut z_evaluations = vec![Fr::one(); n + 1];
+    for i in 0..n {
+        let mut numerator = Fr::one();
+        let mut denominator = Fr::one();
+        for j in 0..3 {
+            numerator *= wire_evaluations[j][i] + beta * label(j, i) + gamma;
+            denominator *= wire_evaluations[j][i] + beta * sigma[j][i] + gamma;
+        }
+        z_evaluations[i + 1] = z_evaluations[i] * numerator * denominator.inverse().unwrap();
+    }
+    assert_eq!(
+        z_evaluations.pop(),
+        Some(Fr::one()),
+        "the permutation does not hold"
+    );
+    let blinding = poly(vec![Fr::rand(rng), Fr::rand(rng), Fr::rand(rng)]);
+    let z = &(&vanishing * &blinding) + &interpolate(&z_evaluations);
+    let cz = srs.commit(&z);
+    transcript.append(&cz);
+    let alpha = transcript.challenge();
+
+    // Round 3: the quotient polynomial, split in three.
+    let x = linear(Fr::one(), Fr::zero());
+    let zw = shift(&z, omega);
+    let gate =
+        &(&(&(&(&(a * b) * &q[0]) + &(a * &q[1])) + &(b * &q[2])) + &(c * &q[3])) + &(&pi + &q[4]);
+    let wire_factor =
+        |wire: &Poly, shifted: &Poly| &(wire + &scale(shifted, beta)) + &constant(gamma);
+    let identity = &(&wire_factor(a, &x) * &wire_factor(b, &scale(&x, k1)))
+        * &(&wire_factor(c, &scale(&x, k2)) * &z);
+    let permuted =
+        &(&wire_factor(a, &s[0]) * &wire_factor(b, &s[1])) * &(&wire_factor(c, &s[2]) * &zw);
+    let mut first_lagrange = vec![Fr::zero(); n];
+    first_lagrange[0] = Fr::one();
+    let l0 = interpolate(&first_lagrange);
+    let numerator = &(&gate + &scale(&(&identity - &permuted), alpha))
+        + &scale(&(&(&z - &constant(Fr::one())) * &l0), alpha.square());
+    let (t, remainder) = numerator.divide_by_vanishing_poly(domain).unwrap();
+    assert!(remainder.is_zero(), "the quotient has a remainder");
+    let mut t = t.coeffs;
+    t.resize(3 * n + 6, Fr::zero());
+    let t_lo = poly(t[..n].to_vec());
+    let t_mid = poly(t[n..2 * n].to_vec());
+    let t_hi = poly(t[2 * n..].to_vec());
+    let (ct_lo, ct_mid, ct_hi) = (srs.commit(&t_lo), srs.commit(&t_mid), srs.commit(&t_hi));
+    transcript.append(&ct_lo);
+    transcript.append(&ct_mid);
+    transcript.append(&ct_hi);
+    let zeta = transcript.challenge();
+
+    // Round 4: the evaluations.
+    let evaluations = [
+        a.evaluate(&zeta),
+        b.evaluate(&zeta),
+        c.evaluate(&zeta),
+        s[0].evaluate(&zeta),
+        s[1].evaluate(&zeta),
+        z.evaluate(&(zeta * omega)),
+    ];
+    let [ae, be, ce, s1e, s2e, zwe] = evaluations;
+    for evaluation in &evaluations {
+        transcript.append(evaluation);
+    }
+    let v = transcript.challenge();
+
+    // Round 5: the linearisation polynomial and the opening proofs.
+    let zeta_n = zeta.pow([n as u64]);
+    let zh_zeta = zeta_n - Fr::one();
+    let l0_zeta = l0.evaluate(&zeta);
+    let pi_zeta = pi.evaluate(&zeta);
+    let mut r = &(&(&(&scale(&q[0], ae * be) + &scale(&q[1], ae)) + &scale(&q[2], be))
+        + &scale(&q[3], ce))
+        + &(&q[4] + &constant(pi_zeta));
+    let identity = alpha
+        * (ae + beta * zeta + gamma)
+        * (be + beta * k1 * zeta + gamma)
+        * (ce + beta * k2 * zeta + gamma);
+    r = &r + &scale(&z, identity);
+    let permuted = alpha * (ae + beta * s1e + gamma) * (be + beta * s2e + gamma) * zwe;
+    r = &r - &scale(&(&scale(&s[2], beta) + &constant(ce + gamma)), permuted);
+    r = &r + &scale(&(&z - &constant(Fr::one())), alpha.square() * l0_zeta);
+    let t = &(&t_lo + &scale(&t_mid, zeta_n)) + &scale(&t_hi, zeta_n.square());
+    r = &r - &scale(&t, zh_zeta);
+    assert!(r.evaluate(&zeta).is_zero(), "r(zeta) is not zero");
+
+    let mut w = r;
+    let mut power = Fr::one();
+    for (polynomial, evaluation) in [(a, ae), (b, be), (c, ce), (&s[0], s1e), (&s[1], s2e)].iter() {
+        power *= v;
+        w = &w + &scale(&(*polynomial - &constant(*evaluation)), power);
+    }
+    let w_zeta = divide_linear(&w, zeta);
+    let w_zeta_omega = divide_linear(&(&z - &constant(zwe)), zeta * omega);
+
+    let mut proof = vec![];
+    for point in [
+        ca,
+        cb,
+        cc,
+        cz,
+        ct_lo,
+        ct_mid,
+        ct_hi,
+        srs.commit(&w_zeta),
+        srs.commit(&w_zeta_omega),
+    ]
+    .iter()
+    {
+        point.write(&mut proof).unwrap();
+    }
+    for evaluation in &evaluations {
+        evaluation.write(&mut proof).unwrap();
+    }
+    assert_eq!(proof.len(), 777);
+    (vk, proof)
+}
+
+fn main() {
+    let mut rng = StdRng::seed_from_u64(2022);
+    let srs = Srs::new(&mut rng);
+    let (k1, k2) = (Fr::from(2u64), Fr::from(3u64));
+    let values = |values: &[u64]| values.iter().map(|value| Fr::from(*value)).collect();
+
+    // z = x * y and w = x + y, on the variables [z, w, x, y].
+    for &(x, y) in [(3u64, 5u64), (7, 11)].iter() {
+        let circuit = Circuit {
+            n: 8,
+            public: 2,
+            values: values(&[x * y, x + y, x, y]),
+            gates: vec![
+                Gate {
+                    selectors: [0, 1, 0, 0, 0],
+                    wires: [0, FREE, FREE],
+                },
+                Gate {
+                    selectors: [0, 1, 0, 0, 0],
+                    wires: [1, FREE, FREE],
+                },
+                Gate {
+                    selectors: [1, 0, 0, -1, 0],
+                    wires: [2, 3, 0],
+                },
+                Gate {
+                    selectors: [0, 1, 1, -1, 0],
+                    wires: [2, 3, 1],
+                },
+            ],
+        };
+        let (vk, proof) = prove(&srs, &circuit, k1, k2, &mut rng);
+        println!("PLONK_VK {:?}", vk);
+        println!("PLONK_PROOF {} {} {}", hex::encode(proof), x * y, x + y);
+    }
+
+    // x^3 + x + 5 = out, on the variables [out, x, x^2, x^3].
+    let x = 3u64;
+    let circuit = Circuit {
+        n: 4,
+        public: 1,
+        values: values(&[x * x * x + x + 5, x, x * x, x * x * x]),
+        gates: vec![
+            Gate {
+                selectors: [0, 1, 0, 0, 0],
+                wires: [0, FREE, FREE],
+            },
+            Gate {
+                selectors: [1, 0, 0, -1, 0],
+                wires: [1, 1, 2],
+            },
+            Gate {
+                selectors: [1, 0, 0, -1, 0],
+                wires: [2, 1, 3],
+            },
+            Gate {
+                selectors: [0, 1, 1, -1, 5],
+                wires: [3, 1, 0],
+            },
+        ],
+    };
+    let (vk, proof) = prove(&srs, &circuit, k1, k2, &mut rng);
+    println!("PLONK_VK_CUBIC {:?}", vk);
+    println!(
+        "PLONK_PROOF_CUBIC {} {}",
+        hex::encode(proof),
+        x * x * x + x + 5
+    );
+}
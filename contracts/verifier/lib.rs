@@ -1,4 +1,4 @@
-//! This is a implementation of zero-knowledge proof verification based on groth16. 
+//! This is a implementation of zero-knowledge proof verification based on groth16 and PLONK.
 //!
 //! ## Warning
 //!
@@ -8,78 +8,640 @@
 //! ## Overview
 //!
 //! This contract demonstrates how to verify zero-knowledge proof on-chain.
+//!
+//! ## Verification Key
+//!
+//! The verification key is stored in the contract, so a new trusted setup does not require
+//! redeploying the verifier and the relayer pointing at it. The admin, i.e. the account that
+//! instantiated the contract, replaces it by `set_verification_key`. If the contract was
+//! instantiated with a timelock, the new key only takes effect after that many blocks, so users
+//! can review it by `pending_verification_key` and `verification_key_hash` in the meantime.
+//!
+//! ## Circuits
+//!
+//! Verification keys are stored per circuit ID, so one verifier serves several circuits. The
+//! withdraw circuit is registered with ID `WITHDRAW_CIRCUIT_ID` on instantiation, and the admin
+//! registers further circuits by `register_circuit`, together with their proving scheme, curve
+//! and number of public inputs.
+//!
+//! A circuit is proven over BN254, as the withdraw circuit, or over BLS12-381. The coordinates of
+//! the proof and verification key take 32 bytes over BN254 and 48 bytes over BLS12-381. Public
+//! inputs take 32 bytes over both curves, as both scalar fields are below 2^256, and are
+//! rejected if they are not below the modulus of the circuit's scalar field.
+//!
+//! A circuit is proven by groth16, which needs a trusted setup of its own, or by PLONK over BN254,
+//! whose verification key is derived from a universal setup shared by all circuits. See the
+//! `plonk` module for the encoding of PLONK keys and proofs.
+//!
+//! `verify_circuit` verifies a proof of any registered circuit against its public inputs, each a
+//! field element in 32 little-endian bytes. `verify` packs the public inputs of the withdraw
+//! circuit and verifies against it. `verify_batch` verifies several proofs of a circuit with a
+//! single pairing check, which is cheaper than verifying them one by one.
+//!
+//! Proofs are passed as bytes, and the public inputs of `verify` as 32 little-endian bytes. A
+//! verifier built with the `legacy-hex-abi` feature takes them as hex strings instead, with the
+//! public inputs in big-endian byte order, for clients of the old ABI.
+//!
+//! ## Chain Extension
+//!
+//! Pairings take most of the time of verification in Wasm. A verifier built with the
+//! `chain-extension` feature runs in `ZkEnvironment`, whose chain extension checks BN254 pairings
+//! in the runtime, and computes MiMC and Poseidon hashes for contracts which need them. Proofs
+//! over BLS12-381 are still checked in Wasm, and without the feature the verifier checks every
+//! proof in Wasm as before, for runtimes without the chain extension.
+//!
+//! ## Error Handling
+//!
+//! Verification returns `Ok(false)` for a well-formed proof which does not verify, and a
+//! `VerifierError` for malformed proofs and public inputs, an unknown circuit or a wrong number of
+//! public inputs, so callers can tell a bad proof from a verifier failure, which is reported as
+//! `PairingFailed`.
 
 // This contract inspired by [zkMega from patractlabs](https://github.com/patractlabs/zkmega).
 
 #![cfg_attr(not(feature = "std"), no_std)]
 
-pub use self::verifier::{Verifier, VerifierRef};
+pub use self::verifier::{Verifier, VerifierError, VerifierRef};
 
 use ink_lang as ink;
+pub mod batch;
 pub mod constants;
+pub mod extension;
+pub mod plonk;
+pub mod proof;
 
-#[ink::contract]
+/// The environment of the verifier, with the chain extension if the `chain-extension` feature is
+/// enabled.
+#[cfg(feature = "chain-extension")]
+pub type VerifierEnvironment = extension::ZkEnvironment;
+
+/// The environment of the verifier, with the chain extension if the `chain-extension` feature is
+/// enabled.
+#[cfg(not(feature = "chain-extension"))]
+pub type VerifierEnvironment = ink_env::DefaultEnvironment;
+
+#[ink::contract(env = crate::VerifierEnvironment)]
 mod verifier {
-    use crate::constants::{VK, VK_GAMMA_ABC};
+    use crate::batch;
+    use crate::constants::{VK, VK_GAMMA_ABC, WITHDRAW_NUM_PUBLIC_INPUTS};
+    use crate::plonk;
+    use crate::proof::{self, EncodedInput, EncodedProof, Engine, Proof};
+    use ark_bls12_381::Bls12_381;
+    use core::convert::TryInto;
     use ink_prelude::{string::String, vec, vec::Vec};
-    // use ink_storage::traits::SpreadAllocate;
+    use ink_storage::{
+        traits::{PackedLayout, SpreadAllocate, SpreadLayout},
+        Mapping,
+    };
+    use scale::{Decode, Encode};
     use zkmega_arkworks::{curve::Bn254, groth16};
 
+    /// A circuit ID.
+    pub type CircuitId = u32;
+
+    /// The ID of the withdraw circuit, which is registered on instantiation.
+    pub const WITHDRAW_CIRCUIT_ID: CircuitId = 0;
+
+    /// The pairing-friendly curve a circuit is proven over.
+    #[derive(Encode, Decode, Debug, PartialEq, Eq, Copy, Clone, SpreadLayout, PackedLayout)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink_storage::traits::StorageLayout)
+    )]
+    pub enum Curve {
+        Bn254,
+        Bls12_381,
+    }
+
+    impl Curve {
+        /// Returns the length of an encoded coordinate.
+        fn field_length(self) -> usize {
+            match self {
+                Curve::Bn254 => <ark_bn254::Bn254 as Engine>::FIELD_LENGTH,
+                Curve::Bls12_381 => <Bls12_381 as Engine>::FIELD_LENGTH,
+            }
+        }
+    }
+
+    /// The proving scheme of a circuit.
+    #[derive(Encode, Decode, Debug, PartialEq, Eq, Copy, Clone, SpreadLayout, PackedLayout)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink_storage::traits::StorageLayout)
+    )]
+    pub enum ProvingScheme {
+        Groth16,
+        Plonk,
+    }
+
+    /// A verification key, as big-endian hex encoded field elements.
+    #[derive(Encode, Decode, Debug, PartialEq, Eq, Clone, SpreadLayout, PackedLayout)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink_storage::traits::StorageLayout)
+    )]
+    pub struct VerificationKey {
+        /// The alpha, beta, gamma and delta points of a groth16 key, 14 field elements, or the
+        /// 23 field elements of a PLONK key.
+        pub vk: Vec<String>,
+        /// The gamma_abc points of a groth16 key, 2 field elements per public input plus one
+        /// point, which a PLONK key doesn't have.
+        pub gamma_abc: Vec<String>,
+    }
+
+    /// A registered circuit.
+    #[derive(Encode, Decode, Debug, PartialEq, Eq, Clone, SpreadLayout, PackedLayout)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink_storage::traits::StorageLayout)
+    )]
+    pub struct Circuit {
+        /// The verification key in use.
+        pub verification_key: VerificationKey,
+        /// The new verification key and the block from which it is used.
+        pub pending_verification_key: Option<(VerificationKey, BlockNumber)>,
+        /// The number of public inputs of a proof.
+        pub num_public_inputs: u32,
+        /// The curve the circuit is proven over.
+        pub curve: Curve,
+        /// The proving scheme of the circuit.
+        pub scheme: ProvingScheme,
+    }
+
+    impl Circuit {
+        /// Returns the pending verification key once its timelock has expired, or the current one.
+        fn active_verification_key(&self, block_number: BlockNumber) -> &VerificationKey {
+            match &self.pending_verification_key {
+                Some((verification_key, effective_at)) if *effective_at <= block_number => {
+                    verification_key
+                }
+                _ => &self.verification_key,
+            }
+        }
+    }
+
+    #[derive(Encode, Decode, Debug, PartialEq, Eq, Copy, Clone)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub enum Error {
+        /// The caller is not the admin of the contract.
+        NotAdmin,
+        /// The verification key does not have the elements of its proving scheme, such as 14 vk
+        /// elements and 2 gamma_abc elements per public input plus one point for groth16, or an
+        /// element is not a hex encoded coordinate of the curve.
+        InvalidVerificationKey,
+        /// A circuit is already registered with the circuit ID.
+        CircuitExists,
+        /// No circuit is registered with the circuit ID.
+        CircuitNotFound,
+        /// The proving scheme is not supported over the curve.
+        UnsupportedCurve,
+    }
+
+    /// The reason a proof could not be verified.
+    ///
+    /// All variants but `PairingFailed` are caused by the caller, with malformed proofs or public
+    /// inputs or an unknown circuit, whereas `PairingFailed` means that the verifier itself failed.
+    #[derive(Encode, Decode, Debug, PartialEq, Eq, Copy, Clone)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub enum VerifierError {
+        /// The proof or a public input is not valid hex.
+        InvalidHex,
+        /// The proof does not have the length of three encoded points.
+        BadProofLength,
+        /// A public input or an evaluation of a PLONK proof is not an element of the scalar field.
+        InputNotInField,
+        /// A point of the proof is not on the curve.
+        PointNotOnCurve,
+        /// The pairing check could not be computed with the verification key.
+        PairingFailed,
+        /// A point of the proof is not in the prime order subgroup.
+        PointNotInSubgroup,
+        /// No circuit is registered with the circuit ID.
+        CircuitNotFound,
+        /// The number of public inputs does not match the circuit.
+        BadInputLength,
+    }
+
+    /// Event emitted when a circuit is registered, or a new verification key is set.
+    ///
+    /// The key is used from block `effective_at` on.
+    #[ink(event)]
+    pub struct VerificationKeyUpdated {
+        #[ink(topic)]
+        circuit_id: CircuitId,
+        #[ink(topic)]
+        verification_key_hash: [u8; 32],
+        effective_at: BlockNumber,
+    }
+
     #[ink(storage)]
-    // #[derive(SpreadAllocate)]
-    pub struct Verifier {}
+    #[derive(SpreadAllocate)]
+    pub struct Verifier {
+        /// The account allowed to register circuits and set verification keys.
+        admin: AccountId,
+        /// Mapping from circuit ID to circuit.
+        circuits: Mapping<CircuitId, Circuit>,
+        /// The number of blocks before a new verification key takes effect.
+        timelock: BlockNumber,
+    }
 
     impl Verifier {
-        /// Use false as initial value
+        /// Registers the withdraw circuit without timelock
         #[ink(constructor)]
         pub fn default() -> Self {
-            // ink_lang::utils::initialize_contract(|_| {})
-            Self {}
+            Self::new(0)
+        }
+
+        /// Registers the withdraw circuit, and delays new verification keys by `timelock` blocks.
+        ///
+        /// The caller becomes the admin of the contract.
+        #[ink(constructor)]
+        pub fn new(timelock: BlockNumber) -> Self {
+            // This call is required in order to correctly initialize the
+            // `Mapping`s of our contract.
+            ink_lang::utils::initialize_contract(|contract| Self::new_init(contract, timelock))
+        }
+
+        /// Initializes the verifier with the withdraw circuit.
+        fn new_init(&mut self, timelock: BlockNumber) {
+            self.admin = Self::env().caller();
+            self.timelock = timelock;
+            self.circuits.insert(
+                WITHDRAW_CIRCUIT_ID,
+                &Circuit {
+                    verification_key: VerificationKey {
+                        vk: VK.iter().map(Self::encode_coordinate).collect(),
+                        gamma_abc: VK_GAMMA_ABC.iter().map(Self::encode_coordinate).collect(),
+                    },
+                    pending_verification_key: None,
+                    num_public_inputs: WITHDRAW_NUM_PUBLIC_INPUTS,
+                    curve: Curve::Bn254,
+                    scheme: ProvingScheme::Groth16,
+                },
+            );
+        }
+
+        /// Returns the admin of the contract.
+        #[ink(message)]
+        pub fn admin(&self) -> AccountId {
+            self.admin
+        }
+
+        /// Returns the number of blocks before a new verification key takes effect.
+        #[ink(message)]
+        pub fn timelock(&self) -> BlockNumber {
+            self.timelock
+        }
+
+        /// Returns the number of public inputs of the circuit.
+        #[ink(message)]
+        pub fn num_public_inputs(&self, circuit_id: CircuitId) -> Option<u32> {
+            self.circuits
+                .get(circuit_id)
+                .map(|circuit| circuit.num_public_inputs)
+        }
+
+        /// Returns the curve the circuit is proven over.
+        #[ink(message)]
+        pub fn curve(&self, circuit_id: CircuitId) -> Option<Curve> {
+            self.circuits.get(circuit_id).map(|circuit| circuit.curve)
+        }
+
+        /// Returns the proving scheme of the circuit.
+        #[ink(message)]
+        pub fn proving_scheme(&self, circuit_id: CircuitId) -> Option<ProvingScheme> {
+            self.circuits.get(circuit_id).map(|circuit| circuit.scheme)
+        }
+
+        /// Returns the verification key of the circuit in use.
+        #[ink(message)]
+        pub fn verification_key(&self, circuit_id: CircuitId) -> Option<VerificationKey> {
+            self.circuits.get(circuit_id).map(|circuit| {
+                circuit
+                    .active_verification_key(self.env().block_number())
+                    .clone()
+            })
+        }
+
+        /// Returns the new verification key of the circuit and the block from which it is used,
+        /// if it is not used yet.
+        #[ink(message)]
+        pub fn pending_verification_key(
+            &self,
+            circuit_id: CircuitId,
+        ) -> Option<(VerificationKey, BlockNumber)> {
+            self.circuits
+                .get(circuit_id)
+                .and_then(|circuit| circuit.pending_verification_key)
+                .filter(|(_, effective_at)| *effective_at > self.env().block_number())
+        }
+
+        /// Returns the Blake2x256 hash of the SCALE encoded verification key of the circuit in use.
+        #[ink(message)]
+        pub fn verification_key_hash(&self, circuit_id: CircuitId) -> Option<[u8; 32]> {
+            self.verification_key(circuit_id)
+                .map(|verification_key| Self::hash_verification_key(&verification_key))
+        }
+
+        /// Registers a new circuit. Only the admin can register it.
+        ///
+        /// The verification key is used right away.
+        #[ink(message)]
+        pub fn register_circuit(
+            &mut self,
+            circuit_id: CircuitId,
+            scheme: ProvingScheme,
+            curve: Curve,
+            verification_key: VerificationKey,
+            num_public_inputs: u32,
+        ) -> Result<(), Error> {
+            if self.env().caller() != self.admin {
+                return Err(Error::NotAdmin);
+            }
+            if self.circuits.contains(circuit_id) {
+                return Err(Error::CircuitExists);
+            }
+            if scheme == ProvingScheme::Plonk && curve != Curve::Bn254 {
+                return Err(Error::UnsupportedCurve);
+            }
+            Self::check_verification_key(&verification_key, scheme, curve, num_public_inputs)?;
+
+            let verification_key_hash = Self::hash_verification_key(&verification_key);
+            self.circuits.insert(
+                circuit_id,
+                &Circuit {
+                    verification_key,
+                    pending_verification_key: None,
+                    num_public_inputs,
+                    curve,
+                    scheme,
+                },
+            );
+
+            self.env().emit_event(VerificationKeyUpdated {
+                circuit_id,
+                verification_key_hash,
+                effective_at: self.env().block_number(),
+            });
+            Ok(())
         }
 
-        /// Verify the zero-knowledge proof
+        /// Sets a new verification key of the circuit. Only the admin can set it.
+        ///
+        /// The key is used after the timelock, and replaces a key which is not used yet.
+        #[ink(message)]
+        pub fn set_verification_key(
+            &mut self,
+            circuit_id: CircuitId,
+            verification_key: VerificationKey,
+        ) -> Result<(), Error> {
+            if self.env().caller() != self.admin {
+                return Err(Error::NotAdmin);
+            }
+            let mut circuit = self
+                .circuits
+                .get(circuit_id)
+                .ok_or(Error::CircuitNotFound)?;
+            Self::check_verification_key(
+                &verification_key,
+                circuit.scheme,
+                circuit.curve,
+                circuit.num_public_inputs,
+            )?;
+
+            // Keep the pending key if it is already in use.
+            let block_number = self.env().block_number();
+            circuit.verification_key = circuit.active_verification_key(block_number).clone();
+
+            let verification_key_hash = Self::hash_verification_key(&verification_key);
+            // A timelock too large for the block number delays the key for good rather than wrapping.
+            let effective_at = block_number.saturating_add(self.timelock);
+            if self.timelock == 0 {
+                circuit.verification_key = verification_key;
+                circuit.pending_verification_key = None;
+            } else {
+                circuit.pending_verification_key = Some((verification_key, effective_at));
+            }
+            self.circuits.insert(circuit_id, &circuit);
+
+            self.env().emit_event(VerificationKeyUpdated {
+                circuit_id,
+                verification_key_hash,
+                effective_at,
+            });
+            Ok(())
+        }
+
+        /// Verify the zero-knowledge proof of the circuit
+        ///
+        /// The public inputs are field elements in little-endian byte order. Fails if the circuit
+        /// is not registered or the number of public inputs does not match it.
+        #[ink(message)]
+        pub fn verify_circuit(
+            &self,
+            circuit_id: CircuitId,
+            proof: EncodedProof,
+            public_inputs: Vec<[u8; 32]>,
+        ) -> Result<bool, VerifierError> {
+            let proof = proof::decode_proof(proof)?;
+            self.verify_proof(circuit_id, &proof, &public_inputs)
+        }
+
+        /// Verify zero-knowledge proofs of the circuit in one pairing check
+        ///
+        /// Returns `true` if every proof verifies, as separate `verify_circuit` calls would.
+        #[ink(message)]
+        pub fn verify_batch(
+            &self,
+            circuit_id: CircuitId,
+            batch: Vec<(EncodedProof, Vec<[u8; 32]>)>,
+        ) -> Result<bool, VerifierError> {
+            let circuit = self
+                .circuits
+                .get(circuit_id)
+                .ok_or(VerifierError::CircuitNotFound)?;
+            if batch
+                .iter()
+                .any(|(_, public_inputs)| public_inputs.len() != circuit.num_public_inputs as usize)
+            {
+                return Err(VerifierError::BadInputLength);
+            }
+            let batch = batch
+                .into_iter()
+                .map(|(proof, public_inputs)| Ok((proof::decode_proof(proof)?, public_inputs)))
+                .collect::<Result<Vec<_>, VerifierError>>()?;
+            self.verify_proofs(&circuit, &batch)
+        }
+
+        /// Verify the zero-knowledge proof of the withdraw circuit
         #[ink(message)]
         pub fn verify(
             &self,
-            proof: String,
-            root: String,
-            nullifier_hash: String,
+            proof: EncodedProof,
+            root: EncodedInput,
+            nullifier_hash: EncodedInput,
             recipient: AccountId,
             relayer: AccountId,
             fee: u128,
             refund: u128,
-        ) -> bool {
-            // decode nullifier_hash
-            let mut nullifier_vec = hex::decode(nullifier_hash).unwrap();
-            nullifier_vec.reverse();
-            let mut root_vec = hex::decode(root).unwrap();
-            root_vec.reverse();
+        ) -> Result<bool, VerifierError> {
             // concat public inputs
             let inputs = ([
-                root_vec,
-                nullifier_vec,
-                self.buff2input(recipient.as_ref()),
-                self.buff2input(relayer.as_ref()),
+                proof::decode_input(root)?.to_vec(),
+                proof::decode_input(nullifier_hash)?.to_vec(),
+                Self::buff2input(recipient.as_ref()),
+                Self::buff2input(relayer.as_ref()),
                 ([(fee).to_le_bytes(), [0u8; 16]]).concat(),
                 ([(refund).to_le_bytes(), [0u8; 16]]).concat(),
             ])
             .concat();
+            let public_inputs: Vec<[u8; 32]> = inputs
+                .chunks(32)
+                .map(|input| input.try_into().unwrap())
+                .collect();
 
-            // decode proof
-            let proof_vec = hex::decode(proof).unwrap();
-            let proof_and_input = ([proof_vec, inputs]).concat();
+            let proof = proof::decode_proof(proof)?;
+            self.verify_proof(WITHDRAW_CIRCUIT_ID, &proof, &public_inputs)
+        }
+
+        /// Verifies the proof against the verification key of the circuit in use.
+        fn verify_proof(
+            &self,
+            circuit_id: CircuitId,
+            proof: &[u8],
+            public_inputs: &[[u8; 32]],
+        ) -> Result<bool, VerifierError> {
+            let circuit = self
+                .circuits
+                .get(circuit_id)
+                .ok_or(VerifierError::CircuitNotFound)?;
+            if public_inputs.len() != circuit.num_public_inputs as usize {
+                return Err(VerifierError::BadInputLength);
+            }
+            // zkmega verifies groth16 proofs over BN254 only, and in Wasm.
+            if cfg!(feature = "chain-extension")
+                || (circuit.scheme, circuit.curve) != (ProvingScheme::Groth16, Curve::Bn254)
+            {
+                return self.verify_proofs(&circuit, &[(proof.to_vec(), public_inputs.to_vec())]);
+            }
+            // Inputs above the modulus would alias smaller ones
+            for input in public_inputs {
+                proof::decode_fr::<ark_bn254::Bn254>(input)?;
+            }
+            Proof::<ark_bn254::Bn254>::decode(proof)?;
+            let proof_and_input = ([proof, &public_inputs.concat()]).concat();
 
             // verify proof
+            let verification_key = circuit.active_verification_key(self.env().block_number());
+            let vk: [&str; 14] = verification_key
+                .vk
+                .iter()
+                .map(|element| element.as_str())
+                .collect::<Vec<&str>>()
+                .try_into()
+                .expect("verification key has 14 elements");
+            let vk_gamma_abc: Vec<&str> = verification_key
+                .gamma_abc
+                .iter()
+                .map(|element| element.as_str())
+                .collect();
             groth16::preprocessed_verify_proof::<Bn254>(
-                VK,
-                VK_GAMMA_ABC.to_vec(),
+                vk,
+                vk_gamma_abc,
                 proof_and_input.as_slice(),
             )
-            .unwrap()
+            .map_err(|_| VerifierError::PairingFailed)
+        }
+
+        /// Verifies the proofs against the verification key of the circuit in use, in one pairing
+        /// check of the proving scheme and curve of the circuit.
+        fn verify_proofs(
+            &self,
+            circuit: &Circuit,
+            batch: &[(Vec<u8>, Vec<[u8; 32]>)],
+        ) -> Result<bool, VerifierError> {
+            let mut seed = [0u8; 32];
+            ink_env::hash_encoded::<ink_env::hash::Blake2x256, _>(&batch, &mut seed);
+
+            let verification_key = circuit.active_verification_key(self.env().block_number());
+            match (circuit.scheme, circuit.curve) {
+                (ProvingScheme::Groth16, Curve::Bn254) => {
+                    batch::decode_and_verify::<ark_bn254::Bn254>(verification_key, batch, &seed)
+                }
+                (ProvingScheme::Groth16, Curve::Bls12_381) => {
+                    batch::decode_and_verify::<Bls12_381>(verification_key, batch, &seed)
+                }
+                (ProvingScheme::Plonk, _) => {
+                    plonk::decode_and_verify(verification_key, batch, &seed)
+                }
+            }
+        }
+
+        /// Returns an error if the verification key does not match the proving scheme and the
+        /// number of public inputs, or has elements which are not coordinates of points on the curve.
+        fn check_verification_key(
+            verification_key: &VerificationKey,
+            scheme: ProvingScheme,
+            curve: Curve,
+            num_public_inputs: u32,
+        ) -> Result<(), Error> {
+            let num_elements = match scheme {
+                ProvingScheme::Groth16 => (VK.len(), 2 * (num_public_inputs as usize + 1)),
+                ProvingScheme::Plonk => (plonk::VK_LENGTH, 0),
+            };
+            if (verification_key.vk.len(), verification_key.gamma_abc.len()) != num_elements {
+                return Err(Error::InvalidVerificationKey);
+            }
+            let is_coordinate = |element: &String| {
+                element.len() == 2 + 2 * curve.field_length()
+                    && element.starts_with("0x")
+                    && element[2..].bytes().all(|byte| byte.is_ascii_hexdigit())
+            };
+            if !verification_key
+                .vk
+                .iter()
+                .chain(&verification_key.gamma_abc)
+                .all(is_coordinate)
+            {
+                return Err(Error::InvalidVerificationKey);
+            }
+            match (scheme, curve) {
+                (ProvingScheme::Groth16, Curve::Bn254) => {
+                    batch::PreparedVerificationKey::<ark_bn254::Bn254>::decode(verification_key)
+                        .map_err(|_| Error::InvalidVerificationKey)?;
+                }
+                (ProvingScheme::Groth16, Curve::Bls12_381) => {
+                    batch::PreparedVerificationKey::<Bls12_381>::decode(verification_key)
+                        .map_err(|_| Error::InvalidVerificationKey)?;
+                }
+                (ProvingScheme::Plonk, _) => {
+                    let verification_key = plonk::PreparedVerificationKey::decode(verification_key)
+                        .map_err(|_| Error::InvalidVerificationKey)?;
+                    // The public inputs are the first gates of a PLONK circuit.
+                    if u64::from(num_public_inputs) > verification_key.n {
+                        return Err(Error::InvalidVerificationKey);
+                    }
+                }
+            }
+            Ok(())
+        }
+
+        /// Returns the big-endian hex encoding of a little-endian field element.
+        fn encode_coordinate(element: &[u8; 32]) -> String {
+            let mut bytes = *element;
+            bytes.reverse();
+            ["0x", &hex::encode(bytes)].concat()
+        }
+
+        /// Returns the Blake2x256 hash of the SCALE encoded verification key.
+        fn hash_verification_key(verification_key: &VerificationKey) -> [u8; 32] {
+            let mut hash = [0u8; 32];
+            ink_env::hash_encoded::<ink_env::hash::Blake2x256, _>(verification_key, &mut hash);
+            hash
         }
 
         // Convert buff to input
-        pub fn buff2input(&self, buffer: &[u8]) -> Vec<u8> {
+        pub fn buff2input(buffer: &[u8]) -> Vec<u8> {
             let result: Vec<Vec<u8>> = buffer
                 .chunks(16)
                 .map(|m| {
@@ -98,27 +660,1192 @@ mod verifier {
         /// Imports all the definitions from the outer scope so we can use them here.
         use super::*;
         use ink_lang as ink;
+
+        const PROOF: &str = "d99fe3bfff6c0fb054febe6f484220320f1f12e32a7f12606731fdbc34dce81fb5b988a199ef9782a04fb6775a2ea82ccb002f483deca7ac63c2a8586d8cdc04005d4d217dd66ff559e986bb6b90a9f43b7dacd9c12d0d3342983b15c82ee7221a08cfd18355c38cc2cff8fc85d50915d960475dc4c1c07370dcf4a00a90b843223792df6a1e0f21b81a86e15db434e5a371e58f7818328355b3f235cf547afc2205985c8e6d0389a5b5b186c0541ad9187388e696d2fd97ddc394ba0908344929008470d0c05e20cbef4cb0b2baff948bbc5b9317d5a8524b20431082b7128d4202b535196cb47e02b79a94dd674ad38d8da9e64fe0b975ed662a2b3abb36ff572400";
+        const ROOT: &str = "222eddf0a52aada170d89dd492bf939c6430d4e10c0bf2b843e6bde7ac46781f";
+        const NULLIFIER_HASH: &str =
+            "15bd4d1ea3140c2a717b781050a6dd46f93a056f8a7e2f40cfd30740a2444a95";
+        const RECIPIENT: [u8; 32] = [
+            212, 53, 147, 199, 21, 253, 211, 28, 97, 20, 26, 189, 4, 169, 159, 214, 130, 44, 133,
+            88, 133, 76, 205, 227, 154, 86, 132, 231, 165, 109, 162, 125,
+        ];
+        const RELAYER: [u8; 32] = [
+            142, 175, 4, 21, 22, 135, 115, 99, 38, 201, 254, 161, 126, 37, 252, 82, 135, 97, 54,
+            147, 201, 18, 144, 156, 178, 38, 170, 71, 148, 242, 106, 72,
+        ];
+        const FEE: u128 = 1000000000;
+        const REFUND: u128 = 2000000000;
+        /// The modulus of the BN254 scalar field, and the root plus the modulus.
+        const SCALAR_MODULUS: &str =
+            "30644e72e131a029b85045b68181585d2833e84879b9709143e1f593f0000001";
+        const ROOT_PLUS_MODULUS: &str =
+            "52932c63865c4dcb2928e38b1440ebf98c64bd2985c5634987c8b37b9c467820";
+        /// The G2 point with x = 2 + u, which is on the curve but not in the prime order subgroup.
+        const G2_NOT_IN_SUBGROUP: &str = "0200000000000000000000000000000000000000000000000000000000000000010000000000000000000000000000000000000000000000000000000000000079fb3bd29775c94b79a79b966a1d625f9877b2531659b66770979e9ffadb4420699ded5761d507e32f13ca717dee01200358172d31991222ffe69587f98ced04";
+        /// Proofs of `proofs/proof-1.json` and `proofs/proof-2.json`: proof, root and nullifier hash.
+        const BATCH: [(&str, &str, &str); 2] = [
+            (
+                "0b8fb811799b80695c08f1b8a402c4510b5db7920b4ef869d2c0052687146d2e251d189608e706e0b0138e53cd5b30b86c96911bb2afd2450e34175b66da25120002cf038d211b19beeffc5f40bf3b040f8465e889a9276ae2a9c91f68e27f26045706661761d1de6f9465c46fb142456c0da6aa6c3f947c5475e3b5f97f5cbf058ba572ffeb5db1dbfb7ccbfe22f4e0854523691195016b102395f62946e0e604b4190827dfa8eb7fc20d44e855f8d2e44b1bb05727f88a5894a3d4af0ef1a51700286cb44fd7472cca35db1176e7ac6ba80974ce55afd435d6252a8ea88beeb52a92cb2ad9a8f6e8ff53da1217dd1a7c2f186e071462538d1ae549c0fd0f16532d00",
+                "1c2da9abef9066af00226dd81e17072c110dec53b4635e8f0732e02a05f1410a",
+                "22e3375355820b3136974da0251c2b2353182f1a0331345cf0d091567acdfa4d",
+            ),
+            (
+                "b614570de3ad087fcb5b61b3c259575e258f978de9fa320b387439aa82318915090830d04cacf9f51fdf6f02aa3383646d20ca2c4b83b975e47e20c38be3e32500a69e578875cd967c415b3b62b83f0e6b0fbe7decbf787ab579d82433b649cf2dbe70185f62487f0b810f6dee8da6d56ac59f0953ed37af0cc6e0b7001b576030004cf1f36c6e1c86cee490a924296633e4860902c2f470e5d9edb6f80214c9246f466add6e123e2032250f8fa7596c934c3650e679e0c7cee1b4241263710915001a7cd142171adf3cd125919ea0d52a01ac82396a5b21d9f8e70a3d9e03363e0ecddf44c3ebff9f9305281a31e3c83b0c458086fdd3fa01891de853f1cb32420300",
+                "221f22ea93ae28f142fecd67a323814fe8902440e38e3a7240d3f4f04d455aad",
+                "0c26b5f0fec32a39b13de4be06d40799358795e1014e7b17f3957de53e49cb7e",
+            ),
+        ];
+        const BATCH_FEE: u128 = 500000000000;
+        const BATCH_REFUND: u128 = 500000000000;
+        /// Verification key over BLS12-381 of a circuit proving `x * y = z` and `x + y = w` for the
+        /// public inputs `z` and `w`. The BLS12-381 fixtures are generated by
+        /// `fixtures/src/bin/bls12_381_groth16.rs`, see `fixtures/README.md`.
+        const BLS_VK: [&str; 14] = [
+            "0x1914c5dfb2ab7749f6a77836652ffcf0540d56251f805bc942045cc2e0b610e1d889d9f324ac9682a32de93c7e0ac37e",
+            "0x06cafb34067981a222658dec8ed1192b690ea641122b24a4636958a81f917853468a6d41b51d02703d3c6fe8f73436ff",
+            "0x09005f3da2a9ae6bea4c878bddae91bde7315bd7349f275692e15db1dd2dfa8a6afa0945cd320b81e4a8406aec25baf6",
+            "0x10b10b5b65a40105e6e958cc7044eaab62ff95f9ec6a4e56d620a0dbace819b0a9e4075545e4e486c65d91b9e37a0d4b",
+            "0x06f08aeac5d67db8c3871ec6002b346081453bdc0a11b0ff6706a56efd8d2595bf10ba3fd41b96452536649b9a75233e",
+            "0x16939684e8f275938a001a98397c9786c0b8c41d45b63674f1839c2d7d6dc66a639f28586385dbcb58bbe025d43020cc",
+            "0x18c3925aab7f1ff86487d4971c332db1ee843788b147a07ed550ca6646410494dd8c01ff47ee5a314f0b5baed052b32f",
+            "0x0ded8e79899c68369599cf78f820b168630e6602fa51674b848b02bde9f424868a43b56646a382ee1594ef49116ce1aa",
+            "0x0a7dfe02f21facf88447bb87927d0bc806d3bb10565ca84e7a0d41ea30336efc7ab7757a6f04e6fdeeed32c228b34316",
+            "0x133b1fda62e9dd2fd7fa54156e2408e7f04fb07230fa18ecb2f6a0f3bc768bc63b5f28bdbcfd38236863f68ccc1f422e",
+            "0x15aec9074a5c09e64ed52a0a21f4c32d42cfc7ebd178552927024d1ddbc96e9e78005e6e32092e38450d8f9aa6b59c9c",
+            "0x08377aea2d092421271bf38e2c1fb7b109f00ee065e050d18ed885c1fdc387aa99e1cb5e2f3a4a1b2f347f8bb6e6ebef",
+            "0x09a2af821c4cd942f7a62fb5cf09d9942e9b798319dbd335ff5f543e31453139691ceda8de79cd70334efb2e5937d741",
+            "0x00ab91a37ec127306253270f32dc3f1a549840b25f0743cc101080821fb0f9236d3a090c082db00b5cf52dc9f82d6777",
+        ];
+        const BLS_VK_GAMMA_ABC: [&str; 6] = [
+            "0x01e545ac0944bdd928d39cb8e7cf8f8aa39579a1ab26b213584336c8f36f7519fca0b4e4f9d05cc23a18169c8f48f47d",
+            "0x0a1d3efe729f1aacccdaf78316d2871780c5706774917113c61462355c76e3a674fdb11204449a09987deec565929cbe",
+            "0x15e0c4b5d1d0bf86e91f16c48b0a50b212eabd85a66b5e16685e23c7757295cf75a76fbe81e8a3b27904ef1ac782988c",
+            "0x0ddac9cb06cc7202f178ee45402d5b387808d698832b592fc287b86fb625b95d4d2ea0291491b3f802dffea7cafac6ab",
+            "0x1587cc0d36996ea4a984b9f807bc2ae4d1fd164ebc5bfde8e7c9091a401c6f254f8e406f36e49a016076b1921ca281da",
+            "0x139a399a643303d27df44cf664fcac96a745d44baaea1cb925ccafc48cf9cebe14cc6b593dec386e11f2258a38bf78ff",
+        ];
+        /// Proofs of the BLS12-381 circuit: proof, `z` and `w`.
+        const BLS_PROOFS: [(&str, u64, u64); 2] = [
+            (
+                "721e7600e49a228c08e6037c03389a0f324b3acb7472e2e9a28847ed13b35e1f373f5c026793e212ea22a6df4e419c1093601ed2ad27a1ccc97641d19fa65fc1ef0692450b8290b022a1faeca27d3985d5f69bf2886e0ff20d7a0d71922cc118006d41933bdb9abcbbaa56c220bf634d57b36de25359fe246a14299ff846eaf93ea9dbd627aae9b03b2c8220dbe085200f696f431df658764db16aa290a409afa254c4cf6fb0540a7f45f274b5619fdc73cd8cc3e3815303501adc26fc54db4e0d0d3c2fa96648b3aa50ad110884dcd6556c71b68ee89a4c8a373e931775aa5ec81c99aa5f80c1e031bba59667414b9116eeb196b53ef9bd2d185dcf5404eb4efb57ee9c12f7e28a8e36f5cea207cd4d38b35fcbc6f06a5e48ed68006f700c7a1800c0c680376b50dc8d48dcd4f8207bcd455036a643bf091f960ce7b55bf7eb5ea16978208aeda5a37efca340c37a6db90d8d1b120ef736053c5a4857213270ec07d7ee0c750a830ba72fe3f9b41807dfbd43fd468c368045a397efc2ec05e3150200",
+                15,
+                8,
+            ),
+            (
+                "d7e1c0dfb796cc3bf6f0132527b6a5edb155e2738b3474de33b07290537834a6f5000c38a472dcea976c50e770903e1992fbd79d0241e13816cd14af7fd980c98e95a604a00a5550f4943a9d7b7d8c5441330857cd810cff43353db9a29b11180081bdc7695381dd696f8c36cffd6934e71be00788c3becfdbba28e793003dc601b9c771d6732e15c1d840958bc7be420ee2a4e6811d09719f839a297c560359461842fd5ca2cfc123c6815c97cb8d0290b6ba831a1df8e6b718549e0f59e5350bb4cc954841655855d06709e2c8d5a11a373504e96c73e037189d8250da6c5eac340abe5e80bdd2a4c94a8e632b4ce10a7ac67684600a1cc023fbe394f835c69655e022d4c96c0be7cf11359722988926ca8170ba536d112fa562bda58b076401001765ca931eda4cdb6ee85245d3643170049332d802399380ec8ce48098cc8fc8d5269b0086dd033d923352b935801f12ce14a707a6251f972395e85cb1cd15885a17aec81124b5dd327c98b1b3ccd716ecf625f6ecd6090017a5408888f1561900",
+                77,
+                18,
+            ),
+        ];
+        /// PLONK verification keys, from a common setup of up to 32 powers, of a circuit proving
+        /// `x * y = z` and `x + y = w` in 8 gates for the public inputs `z` and `w`, and of a circuit
+        /// proving `x^3 + x + 5 = out` in 4 gates for the public input `out`. The setup and the
+        /// PLONK fixtures are generated by `fixtures/src/bin/bn254_plonk.rs`.
+        const PLONK_VK: [&str; 23] = [
+            "0x0175d6840aafa3d35afc77a73a825d8781f40cbd145168370c349b6948d2f110",
+            "0x2633e95a4e41b481d523b55b457a697c8ce45e591f133ef2e76aeff1a6f8a5f5",
+            "0x2b4c5a87b1281f708f72453f6593d9a4ee259e0a48b0d82fdcb569f1e3166683",
+            "0x2df12f06f0d11cf1d2a8cdf920eb9590793acad2da9b967d5f298e3e0379ef5b",
+            "0x07c84106982ed30da218717b39595936f6c6c45f28d420127f710c022a5ab993",
+            "0x167328da5f707caf048f103b99e78f8b0f401124dd3d8f13e7540f7f6feb44c7",
+            "0x1cfbd9f3b170b1fb54db2a2f5308eb65c4c1b75e33066f99850f73d61552a6f2",
+            "0x21c0b64fa24b829acd3f19974bba57404ef5458039baed31a4f5fc5e5fe08e3c",
+            "0x0000000000000000000000000000000000000000000000000000000000000000",
+            "0x0000000000000000000000000000000000000000000000000000000000000000",
+            "0x27aca44c6b3b9c2830761caa7c6997f6aa822d88516ad9c654382f4cfc8b9f9f",
+            "0x2544c5163c4ee794d632fb53297e2443d97cb1a445e901034b7f1313104ef183",
+            "0x07e4e44b97d565aa9631ac003cb899228535542056bea61ecd7757e4afca657a",
+            "0x0e16224f633e6c60881a143b413e628927362dc2ac24485e1693f1b344ef85f5",
+            "0x085284c953e7e64eb8355c1e57061431767c04e2ccc48f9baec27c270aa36ecf",
+            "0x13263cced03a956c9d5ec1979bbf0a02b0f8a5d0025d06ab29cb4dde733db90c",
+            "0x260bc8d0c8cee273e24fa40da603347f3c93e7657effaf10758625b12fd4ae66",
+            "0x06041203cd3cbe932abdc3f8089fb867f2fd3c11bb55b26af3008967feca33d9",
+            "0x1fd2d2f6ca74d701cbfd0aefce006c130c1b7b5badb7e123eaaedfd9a47f7de0",
+            "0x134ef6709f2638799d88ee317cc5b8a08afbc6bea91b45b189a714daa2ec1d83",
+            "0x0000000000000000000000000000000000000000000000000000000000000008",
+            "0x0000000000000000000000000000000000000000000000000000000000000002",
+            "0x0000000000000000000000000000000000000000000000000000000000000003",
+        ];
+        const PLONK_VK_CUBIC: [&str; 23] = [
+            "0x203d9eaf31192fbd007e16bac0c1099c7c5808120a68fc6a4a5a7ff8c3d145d2",
+            "0x1185f1976d14765c2a620aadc3bdb21139ab91fe5b3c80d79657103b6a7dc603",
+            "0x11bb20993040c2c996c2e749d4d520f008723be5b64a8e60709de7e0b75716f7",
+            "0x2ec00712ea5f1f929b5c6fc8a9be06bb21ddeae22a7d8897ab73f53a092870ba",
+            "0x040d64808f367f82dedd176fa4ea23474587f298320c5861b5de37412f755142",
+            "0x026b49c1bf07b8b8b31d52a12db6a1269974b370d00651b65ba0ac6035a1a929",
+            "0x054d39ca2c4dd30e22686c3ec475347b1b0409bf22e4c27f33bf92cc52d8538f",
+            "0x01db3f5584d6f384c947e9de618342e9efa97f6c7bd453dc6e539e96df551b52",
+            "0x1a475c14d40f5728ce60264ff60c16e48c946942cd53367712020875636931c9",
+            "0x2b9194c6f1ec74f5ddcca28af9dccd5626c4b65787be338f41b53ddd9b221104",
+            "0x0ad4eb4d2eec5f867c2c91918849e5c762e1295f8d28b6ea4109d5aa91aadf09",
+            "0x19bc7d63e262dad9d379a2e74b82bb7f41c3df09f10d85b9d6c4fe3fd802503c",
+            "0x1412be21641eb730797edba6b6738f013569c27705223357532704946b6dd2bd",
+            "0x0b9f4a8e36c6a3c9c10e9ed8f3b6e8eb84988fbbe8fbe6e10818eaa0170868a4",
+            "0x2b4f381ff2577f6a2200a24538a03f983368147f03b9b95ba0e3d233630b091c",
+            "0x266f13de91e500df42ac29fad528d561c5b44ac618f0044c457907865aa9c3a7",
+            "0x260bc8d0c8cee273e24fa40da603347f3c93e7657effaf10758625b12fd4ae66",
+            "0x06041203cd3cbe932abdc3f8089fb867f2fd3c11bb55b26af3008967feca33d9",
+            "0x1fd2d2f6ca74d701cbfd0aefce006c130c1b7b5badb7e123eaaedfd9a47f7de0",
+            "0x134ef6709f2638799d88ee317cc5b8a08afbc6bea91b45b189a714daa2ec1d83",
+            "0x0000000000000000000000000000000000000000000000000000000000000004",
+            "0x0000000000000000000000000000000000000000000000000000000000000002",
+            "0x0000000000000000000000000000000000000000000000000000000000000003",
+        ];
+        /// PLONK proofs of the first circuit: proof, `z` and `w`.
+        const PLONK_PROOFS: [(&str, u64, u64); 2] = [
+            (
+                "3d3ef81b549e191d53f8b8200b74072ce6445524d0bcbc2a40d5e467e9370907a245cd3b9e214978947226838079852f0a58ccb92e9db6f9ad223d387d81a714002392921530d4d9ef04c46f508c7d3c7af18406ed60e571e7ad03ae6129c3640f75cb4909f094c31df146ef0825e5a4af27ca443771f8839cbaf9aab51bece11b00a3a4bcc4637c543eec69472e75a8194c1ca97225fb5fe7b4660de9977ca0070c5cdbae882e974177c9cc7037fa62e57cbf8b4efdcc9abf2f0c744059305aea2200f753ffdd03a46978a2b656e17a32c49d5231ada65df2d85fc155987c301a5625c12bae77bbf238f096417ae2ae2258d62ff7751e874ba0d9a5c33bdec30aab0b00902aecc7b1d4beccfb59c4013ef7443b88013f04d9ca22266d0311e5952edb0f42b02e996d7680b4dd2bd305717bb7d5b2fa8d9a45b195d357182e00f96ebf050005e9ad1e1afb3d95d796ef861bb80cdc2d52ac22208b4e34d8dfb419df02f2244379297d0705ea89ab1d062fb805407e4635a64261033ca4f19b171c7df25025001ca2bd31c8b8c8ce87c8519d58f496749bc2ba188c750c1b250339ae22303c1c55eceac00270f6aa12fd4c220eba7d1727b1aa0e81b61d277e5939426ad9aa0600b08f1295e44d13032f572e548c159b0c1d5ce513df02f8665ebee8e5f30cde2578ef83f78ebc3aa01b8893f390014f3b10d8918c38807efbd3b3571e79ab1d0000a7affdc6eb68aff5b904a388c4c5cbc9ad1d3fec868bcbe3cde11f74225a3d0b2e34073a1872896fd3846fe621b62037b843a57c7eae879bd877d446f33ce10d00e8837078b1f5d847a994ff140b7b9710aaca9b092d3b3c5a2a34c7348438ec263ce03cfd07bd710301484fcc1da58d9de0a8ca853bd1a9cac81f4255f967082ca4bc65ba4f676e4e549b8a0fe39e9f9f3f4899530be823773c2c755bbad44e0ebc8b63c93f1fa345ab4336aea2d33208377ae5cbf26fa054c4a954199881e50ea193316e0b49b539e82eb049a7dfadeca6c959b91464ff84547c37a216d82a1f7810aadbadf9a0c27aa93c75078bc66d6440e1a182c831524f615d8893cfa21e",
+                15,
+                8,
+            ),
+            (
+                "75039800cc0a6fed7026f2bc83cf364e539adc7803e388ce8229a1a8953b0b19ab212dd754d63f6a71d2d5118edd3e9727319e2415b519cbb0f03e52061ba01200af51269fa1a88cfe6d3d6d5a6fd2c8aaedb824ad3f1b84456bed0b866e81500b096db08ab78bfa85b6cfbcaa9c5d0740c5ac84ba10e50c0c8cc3f0dc63d0311c00e3db5f02aec93237d2deb09bdea9b7782243f7efed2ff3b2fe6e428c211aaf07f0104a4bc418f8659c2560d787e77f68649579b3f53dc9ecc7c72d82d5a48e1e00a259d8f6d81c6cd02d9af60dfa72014712fa4d311e7c4298472337f9dc5c892a8ea18264d13f668caa23cd28b6cbee2c3b7e4ab2aee197e02b73e02eb979d11700aceda1cac3307ff4939c652481ef69d5309c26c235eaeadd69c63e24743c892b726c4622a6ee9183336b00fae2a9c6e98b1d932f141f2e3d0ae33fd3f1b3a003004e85800cbfd0f6b8fa459ba79ce122a916a9ad755f5329e774bd9963cd04902495c2e58b81d582de91c30351b4d1307c7d622e543ae94b145e27d06e53581e0600380f6eeee06cf7c2969dad178778919bfd473f391d86322e0dcd04987aac91124323c0ab1daaa82de66fd08b27d173aa3d744e0d15190d67285dc39e0ef537160035d43f5904ad3fd59299a32ac655278170fec4af51f3ba512d58021158f034011cfaf7f0f4b58b3f5db8f1391cde4abc6b43321c41da46933825aba1825eda16000aac0960bf3da1560aa9a5624e29521700252f0bb2fa9edbadf0774115f9092b6d602fd91eee4759f1bbc62d63a971bd13536b91df2df850e3c1b04ca6a80713004cd66bc69e79584d006e20bf32a464cf09bedfc1333447c79b89db140ffba20aeaa510bb7877524ebcb2fc9e4795be75cf1eae0870b6971c561f3b0d320b5102a12206f8ee163444978d58065bbc0c0013a70f7bc1b842d10e6670327651a615305cead51084d5fe04058e8f1d4edf3e9dd8588a57da1981589463f7c52ad003b8c2cba3a292e05ba51d7cdc3433203ddfde24701dcf3d3514ad2fb8ee88df0cf020d90c1a753e3351d04208791a34a9dea529471d9ddda1a792bd1add79c416",
+                77,
+                18,
+            ),
+        ];
+        /// A PLONK proof of the cubic circuit: proof and `out`.
+        const PLONK_PROOF_CUBIC: (&str, u64) = (
+            "098261c8b2638c9ffd2420ceeb39e4d216ccc592ad7522f6b3f9cbde9648362daf060e32f6782e1a67fd765225b503be34db0d9a6968d97bce70d6145b3d870d002815263210dc612617c7a9886b4b6abea355be00866e749091a2ed1c2bec85200c2cc7568ffecb289c0819fdac27fc2d459686c9a249e99daf0893c57fa8fc160018d135b5245b57b5e4758939cbf3b6097333e5013fd399b20976c68ad5394105bbe1317b937ee586d7e77694c69d35edcae9521f6667e003f09b863f327b620a00b97671534db01862b64b37294046932b3f260a610b3ce66e716950bb8fc01d0d1e878aa3ca798cd047bfcf3d6c6ce2e16162260b39f96bd1f6626493c3c1871900914ef3e94c0c687f498e3c523ae01a8e8bfd6072f6c25a0ca94c2c4eb998142393a4e8921ec9807e39b5f01b10b21f8a299bc627fb511cbf964aaaa257fad622008f2d648714012dba4d712a5d66ccd41a2a2b89e0f6ac5752893c428a21b3cc141e9a87e49efba498038e876adb81b6f6c0565c6e8fbae06d4b7c44b78efb5b1d006fc3d31d93237ca51e2293e1ee271cfd82b08a89300ca3739ccbb130e83a750dbaa59bc0bdd2151135a2a45ab9eaab98345ab0e88d71b1845cf76dc3f4cecd01006e4c76641de3cfdcc00974375aa16afaca1f071455ef39ec31232a93c8b8df00ef4b1cedf417a50aa1b3cd022bca68025466f0a2e376ee80e93ec744cd518f1e001947966bb81f4b10c854cc20277aa4b55ebabbe1c8be19d901c171fea607400d6519a3139a9a5875ade01716aa55ab5dd73fa9120998e9561dd31fa1ba83b90a00928448a91759f180b4e7613b744dfe6ec132c435670f822d62e33a2920d8741588bf02119c9845df0fe893d8c1d0bd49657fe852ad1e2bdbaa5710fcde7d2515d4083dee04ab201ef40dc38fc41e699c7f61e8ce078669ce08060950dd102e2ed1e27f0866763bedd67872b6df8b34f36371d7033dcc506c8bc5e677f19a341109de845eef5b80d8f48d03c8c898a7a74ab4bc2aff0d3132df99bbc7646b46202c7549d470c6acd52317f8e82d44954d19810e602e2175a748615528bb1d8811",
+            35,
+        );
+        /// The modulus of the BLS12-381 scalar field.
+        const BLS_SCALAR_MODULUS: &str =
+            "73eda753299d7d483339d80809a1d80553bda402fffe5bfeffffffff00000001";
+
         #[ink::test]
         fn test_verify() {
-            let verifier = Verifier::default();
-            let proof = String::from("d99fe3bfff6c0fb054febe6f484220320f1f12e32a7f12606731fdbc34dce81fb5b988a199ef9782a04fb6775a2ea82ccb002f483deca7ac63c2a8586d8cdc04005d4d217dd66ff559e986bb6b90a9f43b7dacd9c12d0d3342983b15c82ee7221a08cfd18355c38cc2cff8fc85d50915d960475dc4c1c07370dcf4a00a90b843223792df6a1e0f21b81a86e15db434e5a371e58f7818328355b3f235cf547afc2205985c8e6d0389a5b5b186c0541ad9187388e696d2fd97ddc394ba0908344929008470d0c05e20cbef4cb0b2baff948bbc5b9317d5a8524b20431082b7128d4202b535196cb47e02b79a94dd674ad38d8da9e64fe0b975ed662a2b3abb36ff572400");
-            let root =
-                String::from("222eddf0a52aada170d89dd492bf939c6430d4e10c0bf2b843e6bde7ac46781f");
-            let nullifier_hash =
-                String::from("15bd4d1ea3140c2a717b781050a6dd46f93a056f8a7e2f40cfd30740a2444a95");
-            let recipient = AccountId::from([
-                212, 53, 147, 199, 21, 253, 211, 28, 97, 20, 26, 189, 4, 169, 159, 214, 130, 44,
-                133, 88, 133, 76, 205, 227, 154, 86, 132, 231, 165, 109, 162, 125,
-            ]);
-            let relayer = AccountId::from([
-                142, 175, 4, 21, 22, 135, 115, 99, 38, 201, 254, 161, 126, 37, 252, 82, 135, 97,
-                54, 147, 201, 18, 144, 156, 178, 38, 170, 71, 148, 242, 106, 72,
-            ]);
-            let fee = 1000000000u128;
-            let refund = 2000000000u128;
-            let result =
-                verifier.verify(proof, root, nullifier_hash, recipient, relayer, fee, refund);
-            assert!(result);
+            let verifier = default_verifier();
+            let result = verifier.verify(
+                encode_proof(&hex::decode(PROOF).unwrap()),
+                encode_input(ROOT),
+                encode_input(NULLIFIER_HASH),
+                AccountId::from(RECIPIENT),
+                AccountId::from(RELAYER),
+                FEE,
+                REFUND,
+            );
+            assert_eq!(result, Ok(true));
+
+            // A proof of other public inputs is rejected.
+            let result = verifier.verify(
+                encode_proof(&hex::decode(PROOF).unwrap()),
+                encode_input(ROOT),
+                encode_input(NULLIFIER_HASH),
+                AccountId::from(RECIPIENT),
+                AccountId::from(RELAYER),
+                FEE + 1,
+                REFUND,
+            );
+            assert_eq!(result, Ok(false));
+        }
+
+        #[ink::test]
+        fn malformed_proof_fails() {
+            let verifier = default_verifier();
+            let verify = |proof: &[u8]| {
+                verifier.verify(
+                    encode_proof(proof),
+                    encode_input(ROOT),
+                    encode_input(NULLIFIER_HASH),
+                    AccountId::from(RECIPIENT),
+                    AccountId::from(RELAYER),
+                    FEE,
+                    REFUND,
+                )
+            };
+            let proof = hex::decode(PROOF).unwrap();
+
+            assert_eq!(verify(&proof[1..]), Err(VerifierError::BadProofLength));
+
+            // Increment the least significant byte of the y coordinate of point a.
+            let mut invalid_proof = proof.clone();
+            invalid_proof[32] = invalid_proof[32].wrapping_add(1);
+            assert_eq!(verify(&invalid_proof), Err(VerifierError::PointNotOnCurve));
+
+            // The infinity flag is either 0 or 1.
+            let mut invalid_proof = proof.clone();
+            invalid_proof[64] = 2;
+            assert_eq!(verify(&invalid_proof), Err(VerifierError::PointNotOnCurve));
+
+            // Replace point b by a point on the curve outside of the prime order subgroup.
+            let mut invalid_proof = proof;
+            invalid_proof[65..193].copy_from_slice(&hex::decode(G2_NOT_IN_SUBGROUP).unwrap());
+            assert_eq!(
+                verify(&invalid_proof),
+                Err(VerifierError::PointNotInSubgroup)
+            );
+        }
+
+        #[ink::test]
+        fn public_input_not_in_field_fails() {
+            let verifier = default_verifier();
+            let verify = |root: &str| {
+                verifier.verify(
+                    encode_proof(&hex::decode(PROOF).unwrap()),
+                    encode_input(root),
+                    encode_input(NULLIFIER_HASH),
+                    AccountId::from(RECIPIENT),
+                    AccountId::from(RELAYER),
+                    FEE,
+                    REFUND,
+                )
+            };
+
+            // The root plus the modulus would alias the root.
+            assert_eq!(
+                verify(ROOT_PLUS_MODULUS),
+                Err(VerifierError::InputNotInField)
+            );
+            assert_eq!(verify(SCALAR_MODULUS), Err(VerifierError::InputNotInField));
+
+            let (proof, mut public_inputs) = withdraw_proof();
+            public_inputs[1] = [0xff; 32];
+            assert_eq!(
+                verifier.verify_circuit(WITHDRAW_CIRCUIT_ID, proof.clone(), public_inputs.clone()),
+                Err(VerifierError::InputNotInField)
+            );
+            assert_eq!(
+                verifier.verify_batch(WITHDRAW_CIRCUIT_ID, vec![(proof, public_inputs)]),
+                Err(VerifierError::InputNotInField)
+            );
+        }
+
+        #[ink::test]
+        fn buff2input_works() {
+            // ZoKrates takes an account as two field elements, its high and low 128 bits, as split
+            // by `scripts/0-generateCommitment.js` in the witness of `proofs/proof-1.json`.
+            let field = |value: u128| ([value.to_le_bytes(), [0u8; 16]]).concat();
+            assert_eq!(
+                Verifier::buff2input(&RECIPIENT),
+                [
+                    field(282074524127326628435517427149838065622),
+                    field(173030805087624539772769074318515479165),
+                ]
+                .concat()
+            );
+            assert_eq!(
+                Verifier::buff2input(&RELAYER),
+                [
+                    field(189659110152103896296452029441145961554),
+                    field(179950539185128938459188096563022031432),
+                ]
+                .concat()
+            );
+
+            // Both halves are below the modulus, whatever the account.
+            for input in Verifier::buff2input(&[0xff; 32]).chunks(32) {
+                assert!(proof::decode_fr::<ark_bn254::Bn254>(input.try_into().unwrap()).is_ok());
+            }
+        }
+
+        #[ink::test]
+        fn withdraw_verification_key_works() {
+            let verification_key = default_verifier()
+                .verification_key(WITHDRAW_CIRCUIT_ID)
+                .unwrap();
+
+            // alpha, as in `build/verification.key`.
+            assert_eq!(
+                verification_key.vk[..2],
+                [
+                    "0x1c5ecfdfd8856b51f73199d3777cfb87de3f789e28d562b018e19717cf55622c",
+                    "0x1ca644679bb6129196c993b5f508bb4ae3e188a407677a00baec2d2c48f5c69a",
+                ]
+            );
+            assert_eq!(
+                Verifier::check_verification_key(
+                    &verification_key,
+                    ProvingScheme::Groth16,
+                    Curve::Bn254,
+                    WITHDRAW_NUM_PUBLIC_INPUTS,
+                ),
+                Ok(())
+            );
+        }
+
+        #[cfg(feature = "legacy-hex-abi")]
+        #[ink::test]
+        fn malformed_hex_fails() {
+            let verifier = default_verifier();
+            let verify = |proof: &str, root: &str| {
+                verifier.verify(
+                    String::from(proof),
+                    String::from(root),
+                    String::from(NULLIFIER_HASH),
+                    AccountId::from(RECIPIENT),
+                    AccountId::from(RELAYER),
+                    FEE,
+                    REFUND,
+                )
+            };
+
+            assert_eq!(
+                verify(&PROOF.replacen("d", "x", 1), ROOT),
+                Err(VerifierError::InvalidHex)
+            );
+            assert_eq!(verify(PROOF, "0x222e"), Err(VerifierError::InvalidHex));
+            assert_eq!(
+                verify(PROOF, &ROOT[2..]),
+                Err(VerifierError::InputNotInField)
+            );
+        }
+
+        #[ink::test]
+        fn set_verification_key_works() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut verifier = default_verifier();
+            let old_hash = verifier.verification_key_hash(WITHDRAW_CIRCUIT_ID).unwrap();
+            let mut verification_key = verifier.verification_key(WITHDRAW_CIRCUIT_ID).unwrap();
+            verification_key.gamma_abc.swap(0, 2);
+
+            // Only the admin can set the verification key.
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(
+                verifier.set_verification_key(WITHDRAW_CIRCUIT_ID, verification_key.clone()),
+                Err(Error::NotAdmin)
+            );
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.alice);
+            let mut invalid_verification_key = verification_key.clone();
+            invalid_verification_key.gamma_abc.truncate(16);
+            assert_eq!(
+                verifier.set_verification_key(WITHDRAW_CIRCUIT_ID, invalid_verification_key),
+                Err(Error::InvalidVerificationKey)
+            );
+            assert_eq!(
+                verifier.set_verification_key(1, verification_key.clone()),
+                Err(Error::CircuitNotFound)
+            );
+            assert_eq!(
+                verifier.verification_key_hash(WITHDRAW_CIRCUIT_ID),
+                Some(old_hash)
+            );
+
+            assert_eq!(
+                verifier.set_verification_key(WITHDRAW_CIRCUIT_ID, verification_key.clone()),
+                Ok(())
+            );
+            assert_eq!(
+                verifier.verification_key(WITHDRAW_CIRCUIT_ID),
+                Some(verification_key)
+            );
+            assert_ne!(
+                verifier.verification_key_hash(WITHDRAW_CIRCUIT_ID),
+                Some(old_hash)
+            );
+            assert_eq!(verifier.pending_verification_key(WITHDRAW_CIRCUIT_ID), None);
+        }
+
+        #[ink::test]
+        fn verification_key_timelock_works() {
+            let mut verifier = Verifier::new(2);
+            let old_verification_key = verifier.verification_key(WITHDRAW_CIRCUIT_ID).unwrap();
+            let mut verification_key = old_verification_key.clone();
+            verification_key.gamma_abc.swap(0, 2);
+
+            assert_eq!(
+                verifier.set_verification_key(WITHDRAW_CIRCUIT_ID, verification_key.clone()),
+                Ok(())
+            );
+            assert_eq!(
+                verifier.pending_verification_key(WITHDRAW_CIRCUIT_ID),
+                Some((verification_key.clone(), 2))
+            );
+
+            // The old key is used until the timelock expires.
+            ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+            assert_eq!(
+                verifier.verification_key(WITHDRAW_CIRCUIT_ID),
+                Some(old_verification_key)
+            );
+
+            ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+            assert_eq!(
+                verifier.verification_key(WITHDRAW_CIRCUIT_ID),
+                Some(verification_key)
+            );
+            assert_eq!(verifier.pending_verification_key(WITHDRAW_CIRCUIT_ID), None);
+        }
+
+        #[ink::test]
+        fn verification_key_timelock_does_not_overflow() {
+            let mut verifier = Verifier::new(BlockNumber::MAX);
+            let old_verification_key = verifier.verification_key(WITHDRAW_CIRCUIT_ID).unwrap();
+            let mut verification_key = old_verification_key.clone();
+            verification_key.gamma_abc.swap(0, 2);
+
+            ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+            assert_eq!(
+                verifier.set_verification_key(WITHDRAW_CIRCUIT_ID, verification_key.clone()),
+                Ok(())
+            );
+            assert_eq!(
+                verifier.pending_verification_key(WITHDRAW_CIRCUIT_ID),
+                Some((verification_key, BlockNumber::MAX))
+            );
+            assert_eq!(
+                verifier.verification_key(WITHDRAW_CIRCUIT_ID),
+                Some(old_verification_key)
+            );
+        }
+
+        #[ink::test]
+        fn register_circuit_works() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut verifier = default_verifier();
+            let verification_key = verifier.verification_key(WITHDRAW_CIRCUIT_ID).unwrap();
+            let circuit_id = 1;
+
+            assert_eq!(
+                verifier.register_circuit(
+                    WITHDRAW_CIRCUIT_ID,
+                    ProvingScheme::Groth16,
+                    Curve::Bn254,
+                    verification_key.clone(),
+                    8
+                ),
+                Err(Error::CircuitExists)
+            );
+            assert_eq!(
+                verifier.register_circuit(
+                    circuit_id,
+                    ProvingScheme::Groth16,
+                    Curve::Bn254,
+                    verification_key.clone(),
+                    7
+                ),
+                Err(Error::InvalidVerificationKey)
+            );
+            // alpha is not on the curve
+            let mut off_curve_key = verification_key.clone();
+            off_curve_key.vk[1] = String::from("0x") + &"0".repeat(63) + "1";
+            assert_eq!(
+                verifier.register_circuit(
+                    circuit_id,
+                    ProvingScheme::Groth16,
+                    Curve::Bn254,
+                    off_curve_key,
+                    8
+                ),
+                Err(Error::InvalidVerificationKey)
+            );
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(
+                verifier.register_circuit(
+                    circuit_id,
+                    ProvingScheme::Groth16,
+                    Curve::Bn254,
+                    verification_key.clone(),
+                    8
+                ),
+                Err(Error::NotAdmin)
+            );
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.alice);
+            assert_eq!(
+                verifier.register_circuit(
+                    circuit_id,
+                    ProvingScheme::Groth16,
+                    Curve::Bn254,
+                    verification_key.clone(),
+                    8
+                ),
+                Ok(())
+            );
+            assert_eq!(verifier.num_public_inputs(circuit_id), Some(8));
+            assert_eq!(verifier.curve(circuit_id), Some(Curve::Bn254));
+            assert_eq!(
+                verifier.verification_key(circuit_id),
+                Some(verification_key)
+            );
+
+            // The withdraw proof verifies against the same key registered with another ID,
+            // but not with a wrong number of public inputs or an unknown circuit.
+            let (proof, public_inputs) = withdraw_proof();
+            assert_eq!(
+                verifier.verify_circuit(circuit_id, proof.clone(), public_inputs.clone()),
+                Ok(true)
+            );
+            assert_eq!(
+                verifier.verify_circuit(circuit_id, proof.clone(), public_inputs[..7].to_vec()),
+                Err(VerifierError::BadInputLength)
+            );
+            assert_eq!(
+                verifier.verify_circuit(2, proof, public_inputs),
+                Err(VerifierError::CircuitNotFound)
+            );
+        }
+
+        #[ink::test]
+        fn verify_batch_works() {
+            let verifier = default_verifier();
+            let mut batch = vec![withdraw_proof()];
+            for (proof, root, nullifier_hash) in BATCH.iter() {
+                batch.push((
+                    encode_proof(&hex::decode(proof).unwrap()),
+                    withdraw_inputs(root, nullifier_hash, BATCH_FEE, BATCH_REFUND),
+                ));
+            }
+            let verify_separately = |batch: &Vec<(EncodedProof, Vec<[u8; 32]>)>| {
+                batch
+                    .iter()
+                    .try_fold(true, |valid, (proof, public_inputs)| {
+                        verifier
+                            .verify_circuit(
+                                WITHDRAW_CIRCUIT_ID,
+                                proof.clone(),
+                                public_inputs.clone(),
+                            )
+                            .map(|result| valid && result)
+                    })
+            };
+
+            assert_eq!(verify_separately(&batch), Ok(true));
+            assert_eq!(
+                verifier.verify_batch(WITHDRAW_CIRCUIT_ID, batch.clone()),
+                Ok(true)
+            );
+            assert_eq!(verifier.verify_batch(WITHDRAW_CIRCUIT_ID, vec![]), Ok(true));
+
+            // One invalid proof fails the batch.
+            let mut invalid_batch = batch.clone();
+            invalid_batch[1].1 =
+                withdraw_inputs(BATCH[0].1, BATCH[0].2, BATCH_FEE + 1, BATCH_REFUND);
+            assert_eq!(verify_separately(&invalid_batch), Ok(false));
+            assert_eq!(
+                verifier.verify_batch(WITHDRAW_CIRCUIT_ID, invalid_batch),
+                Ok(false)
+            );
+
+            // Invalid proofs don't cancel out each other.
+            let mut swapped_batch = batch.clone();
+            swapped_batch.swap(0, 1);
+            swapped_batch[0].1 = batch[0].1.clone();
+            swapped_batch[1].1 = batch[1].1.clone();
+            assert_eq!(verify_separately(&swapped_batch), Ok(false));
+            assert_eq!(
+                verifier.verify_batch(WITHDRAW_CIRCUIT_ID, swapped_batch),
+                Ok(false)
+            );
+
+            // Malformed proofs fail as they do separately.
+            let mut malformed_batch = batch.clone();
+            malformed_batch[2].1.pop();
+            assert_eq!(
+                verify_separately(&malformed_batch),
+                Err(VerifierError::BadInputLength)
+            );
+            assert_eq!(
+                verifier.verify_batch(WITHDRAW_CIRCUIT_ID, malformed_batch),
+                Err(VerifierError::BadInputLength)
+            );
+            assert_eq!(
+                verifier.verify_batch(1, batch),
+                Err(VerifierError::CircuitNotFound)
+            );
+        }
+
+        #[ink::test]
+        fn bls12_381_circuit_works() {
+            let mut verifier = default_verifier();
+            let circuit_id = 1;
+            let verification_key = VerificationKey {
+                vk: BLS_VK.iter().map(|element| element.to_string()).collect(),
+                gamma_abc: BLS_VK_GAMMA_ABC
+                    .iter()
+                    .map(|element| element.to_string())
+                    .collect(),
+            };
+
+            // The coordinates of BLS12-381 don't fit a BN254 key, and vice versa.
+            assert_eq!(
+                verifier.register_circuit(
+                    circuit_id,
+                    ProvingScheme::Groth16,
+                    Curve::Bn254,
+                    verification_key.clone(),
+                    2
+                ),
+                Err(Error::InvalidVerificationKey)
+            );
+            let withdraw_verification_key = verifier.verification_key(WITHDRAW_CIRCUIT_ID).unwrap();
+            assert_eq!(
+                verifier.register_circuit(
+                    circuit_id,
+                    ProvingScheme::Groth16,
+                    Curve::Bls12_381,
+                    withdraw_verification_key,
+                    8
+                ),
+                Err(Error::InvalidVerificationKey)
+            );
+            assert_eq!(
+                verifier.register_circuit(
+                    circuit_id,
+                    ProvingScheme::Groth16,
+                    Curve::Bls12_381,
+                    verification_key,
+                    2
+                ),
+                Ok(())
+            );
+            assert_eq!(verifier.curve(circuit_id), Some(Curve::Bls12_381));
+            assert_eq!(verifier.curve(WITHDRAW_CIRCUIT_ID), Some(Curve::Bn254));
+
+            let batch: Vec<(EncodedProof, Vec<[u8; 32]>)> = BLS_PROOFS
+                .iter()
+                .map(|(proof, z, w)| {
+                    (
+                        encode_proof(&hex::decode(proof).unwrap()),
+                        vec![small_input(*z), small_input(*w)],
+                    )
+                })
+                .collect();
+            for (proof, public_inputs) in batch.iter() {
+                assert_eq!(
+                    verifier.verify_circuit(circuit_id, proof.clone(), public_inputs.clone()),
+                    Ok(true)
+                );
+            }
+            assert_eq!(verifier.verify_batch(circuit_id, batch.clone()), Ok(true));
+
+            // A proof of other public inputs is rejected.
+            let (proof, mut public_inputs) = batch[0].clone();
+            public_inputs[0] = small_input(BLS_PROOFS[0].1 + 1);
+            assert_eq!(
+                verifier.verify_circuit(circuit_id, proof.clone(), public_inputs),
+                Ok(false)
+            );
+            let mut invalid_batch = batch.clone();
+            invalid_batch.swap(0, 1);
+            invalid_batch[0].1 = batch[0].1.clone();
+            assert_eq!(verifier.verify_batch(circuit_id, invalid_batch), Ok(false));
+
+            // Public inputs are checked against the BLS12-381 scalar field, which is smaller.
+            let mut modulus = hex::decode(BLS_SCALAR_MODULUS).unwrap();
+            modulus.reverse();
+            let public_inputs = vec![modulus.try_into().unwrap(), small_input(BLS_PROOFS[0].2)];
+            assert_eq!(
+                verifier.verify_circuit(circuit_id, proof, public_inputs),
+                Err(VerifierError::InputNotInField)
+            );
+
+            // Proofs of one curve don't decode on the other.
+            let (proof, public_inputs) = withdraw_proof();
+            assert_eq!(
+                verifier.verify_circuit(circuit_id, proof, public_inputs[..2].to_vec()),
+                Err(VerifierError::BadProofLength)
+            );
+            assert_eq!(
+                verifier.verify_circuit(
+                    WITHDRAW_CIRCUIT_ID,
+                    batch[0].0.clone(),
+                    withdraw_proof().1
+                ),
+                Err(VerifierError::BadProofLength)
+            );
+        }
+
+        #[ink::test]
+        fn plonk_circuit_works() {
+            let mut verifier = default_verifier();
+            let verification_key = |elements: &[&str]| VerificationKey {
+                vk: elements.iter().map(|element| element.to_string()).collect(),
+                gamma_abc: vec![],
+            };
+            let (circuit_id, cubic_circuit_id) = (1, 2);
+
+            // Both keys come from the same setup, with the same G2 point `[x]`.
+            assert_eq!(PLONK_VK[16..20], PLONK_VK_CUBIC[16..20]);
+
+            assert_eq!(
+                verifier.register_circuit(
+                    circuit_id,
+                    ProvingScheme::Plonk,
+                    Curve::Bls12_381,
+                    verification_key(&PLONK_VK),
+                    2
+                ),
+                Err(Error::UnsupportedCurve)
+            );
+            let groth16_verification_key = verifier.verification_key(WITHDRAW_CIRCUIT_ID).unwrap();
+            assert_eq!(
+                verifier.register_circuit(
+                    circuit_id,
+                    ProvingScheme::Plonk,
+                    Curve::Bn254,
+                    groth16_verification_key,
+                    8
+                ),
+                Err(Error::InvalidVerificationKey)
+            );
+            // The public inputs don't fit in the 8 gates of the circuit.
+            assert_eq!(
+                verifier.register_circuit(
+                    circuit_id,
+                    ProvingScheme::Plonk,
+                    Curve::Bn254,
+                    verification_key(&PLONK_VK),
+                    9
+                ),
+                Err(Error::InvalidVerificationKey)
+            );
+            assert_eq!(
+                verifier.register_circuit(
+                    circuit_id,
+                    ProvingScheme::Plonk,
+                    Curve::Bn254,
+                    verification_key(&PLONK_VK),
+                    2
+                ),
+                Ok(())
+            );
+            assert_eq!(
+                verifier.register_circuit(
+                    cubic_circuit_id,
+                    ProvingScheme::Plonk,
+                    Curve::Bn254,
+                    verification_key(&PLONK_VK_CUBIC),
+                    1
+                ),
+                Ok(())
+            );
+            assert_eq!(
+                verifier.proving_scheme(circuit_id),
+                Some(ProvingScheme::Plonk)
+            );
+            assert_eq!(
+                verifier.proving_scheme(WITHDRAW_CIRCUIT_ID),
+                Some(ProvingScheme::Groth16)
+            );
+
+            let batch: Vec<(EncodedProof, Vec<[u8; 32]>)> = PLONK_PROOFS
+                .iter()
+                .map(|(proof, z, w)| {
+                    (
+                        encode_proof(&hex::decode(proof).unwrap()),
+                        vec![small_input(*z), small_input(*w)],
+                    )
+                })
+                .collect();
+            for (proof, public_inputs) in batch.iter() {
+                assert_eq!(
+                    verifier.verify_circuit(circuit_id, proof.clone(), public_inputs.clone()),
+                    Ok(true)
+                );
+            }
+            assert_eq!(verifier.verify_batch(circuit_id, batch.clone()), Ok(true));
+            let (cubic_proof, out) = PLONK_PROOF_CUBIC;
+            let cubic_proof = encode_proof(&hex::decode(cubic_proof).unwrap());
+            assert_eq!(
+                verifier.verify_circuit(
+                    cubic_circuit_id,
+                    cubic_proof.clone(),
+                    vec![small_input(out)]
+                ),
+                Ok(true)
+            );
+
+            // A proof of other public inputs, or of another circuit, is rejected.
+            let (proof, public_inputs) = batch[0].clone();
+            let swapped_inputs = vec![public_inputs[1], public_inputs[0]];
+            assert_eq!(
+                verifier.verify_circuit(circuit_id, proof.clone(), swapped_inputs.clone()),
+                Ok(false)
+            );
+            let mut invalid_batch = batch.clone();
+            invalid_batch[1].1 = swapped_inputs;
+            assert_eq!(verifier.verify_batch(circuit_id, invalid_batch), Ok(false));
+            assert_eq!(
+                verifier.verify_circuit(circuit_id, cubic_proof, public_inputs.clone()),
+                Ok(false)
+            );
+
+            // Malformed proofs fail as groth16 proofs do.
+            let (withdraw_proof, _) = withdraw_proof();
+            assert_eq!(
+                verifier.verify_circuit(circuit_id, withdraw_proof, public_inputs),
+                Err(VerifierError::BadProofLength)
+            );
+        }
+
+        #[cfg(feature = "chain-extension")]
+        #[ink::test]
+        fn chain_extension_failure_fails() {
+            let verifier = default_verifier();
+            let (proof, public_inputs) = withdraw_proof();
+            assert_eq!(
+                verifier.verify_circuit(WITHDRAW_CIRCUIT_ID, proof.clone(), public_inputs.clone()),
+                Ok(true)
+            );
+
+            // The runtime rejects the input of the pairing check.
+            register_mock_extension(1);
+            assert_eq!(
+                verifier.verify_circuit(WITHDRAW_CIRCUIT_ID, proof.clone(), public_inputs.clone()),
+                Err(VerifierError::PairingFailed)
+            );
+
+            // An unknown status code fails the verification rather than the contract.
+            register_mock_extension(2);
+            assert_eq!(
+                verifier.verify_circuit(WITHDRAW_CIRCUIT_ID, proof, public_inputs),
+                Err(VerifierError::PairingFailed)
+            );
+        }
+
+        #[cfg(feature = "chain-extension")]
+        #[ink::test]
+        fn chain_extension_hashes_work() {
+            use crate::extension::{ZkExtension, ZkExtensionError};
+            use ink_lang::ChainExtensionInstance;
+
+            register_mock_extension(0);
+            let instance = || <ZkExtension as ChainExtensionInstance>::instantiate();
+            let element = |hex: &str| -> [u8; 32] {
+                let mut bytes = hex::decode(hex).unwrap();
+                bytes.reverse();
+                bytes.try_into().unwrap()
+            };
+
+            // The zero subtrees of the relayer, whose MiMC is computed in Wasm, hash to the next.
+            let zeros = [
+                "0000000000000000000000000000000000000000000000000000000000000000",
+                "2d9fea8398a61ea1997e7d748364c0fdb49412c4dbabc1578375ade642e85581",
+                "1234a304a6250851669d511fd01a93eef2fd88d84bbb8b089021393bd6314ace",
+                "11a759c3e46852e6ee14e3bb8f7158c62d9270217563f56726b3d5ae719e77cf",
+            ];
+            for level in zeros.windows(2) {
+                let zero = element(level[0]);
+                assert_eq!(
+                    instance().mimc_sponge(vec![zero, zero]),
+                    Ok(element(level[1]))
+                );
+            }
+
+            // The test vectors of circomlib.
+            assert_eq!(
+                instance().poseidon(vec![small_input(1)]),
+                Ok(element(
+                    "29176100eaa962bdc1fe6c654d6a3c130e96a4d1168b33848b897dc502820133"
+                ))
+            );
+            assert_eq!(
+                instance().poseidon(vec![small_input(1), small_input(2)]),
+                Ok(element(
+                    "115cc0f5e7d690413df64c6b9662e9cf2a3617f2743245519e19607a4417189a"
+                ))
+            );
+
+            // Inputs above the modulus are rejected by the runtime.
+            assert_eq!(
+                instance().mimc_sponge(vec![[0xff; 32]]),
+                Err(ZkExtensionError::InvalidInput)
+            );
+        }
+
+        /// Instantiates the verifier, with a mock of the chain extension if it is enabled.
+        fn default_verifier() -> Verifier {
+            #[cfg(feature = "chain-extension")]
+            register_mock_extension(0);
+            Verifier::default()
+        }
+
+        /// A mock of the chain extension, which computes the BN254 pairing check in Wasm as the
+        /// verifier does without the chain extension, and the hashes with arkworks.
+        #[cfg(feature = "chain-extension")]
+        struct MockZkExtension {
+            /// The function of the chain extension.
+            func_id: u32,
+            /// The status code returned by the runtime, which fails the call if not 0.
+            status_code: u32,
+        }
+
+        /// Registers the mocks of the pairing check, MiMC and Poseidon functions.
+        #[cfg(feature = "chain-extension")]
+        fn register_mock_extension(status_code: u32) {
+            for func_id in 1001..=1003 {
+                ink_env::test::register_chain_extension(MockZkExtension {
+                    func_id,
+                    status_code,
+                });
+            }
+        }
+
+        #[cfg(feature = "chain-extension")]
+        impl ink_env::test::ChainExtension for MockZkExtension {
+            fn func_id(&self) -> u32 {
+                self.func_id
+            }
+
+            fn call(&mut self, input: &[u8], output: &mut Vec<u8>) -> u32 {
+                if self.status_code != 0 {
+                    return self.status_code;
+                }
+                if self.func_id == 1001 {
+                    let pairs = <Vec<(Vec<u8>, Vec<u8>)>>::decode(&mut &input[..])
+                        .unwrap()
+                        .iter()
+                        .map(|(g1, g2)| {
+                            (
+                                proof::decode_g1::<ark_bn254::Bn254>(g1).unwrap(),
+                                proof::decode_g2::<ark_bn254::Bn254>(g2).unwrap(),
+                            )
+                        })
+                        .collect::<Vec<_>>();
+                    let result = proof::pairing_check::<ark_bn254::Bn254>(&pairs).unwrap();
+                    scale::Encode::encode_to(&result, output);
+                    return 0;
+                }
+
+                let inputs = match <Vec<[u8; 32]>>::decode(&mut &input[..])
+                    .unwrap()
+                    .iter()
+                    .map(proof::decode_fr::<ark_bn254::Bn254>)
+                    .collect::<Result<Vec<_>, _>>()
+                {
+                    Ok(inputs) => inputs,
+                    Err(_) => return 1,
+                };
+                let hash = match self.func_id {
+                    1002 => mock_mimc_sponge(&inputs),
+                    _ => match mock_poseidon(&inputs) {
+                        Some(hash) => hash,
+                        None => return 1,
+                    },
+                };
+                let hash: [u8; 32] = ark_ff::to_bytes![hash].unwrap().try_into().unwrap();
+                scale::Encode::encode_to(&hash, output);
+                0
+            }
+        }
+
+        /// Returns `x^5`, the S-box of MiMC and Poseidon.
+        #[cfg(feature = "chain-extension")]
+        fn pow5(x: ark_bn254::Fr) -> ark_bn254::Fr {
+            use ark_ff::Field;
+            x.square().square() * x
+        }
+
+        /// Returns the MiMC sponge hash with 220 rounds and key 0, whose round constants are the
+        /// keccak256 hash chain of `mimcsponge` as in circomlib.
+        #[cfg(feature = "chain-extension")]
+        fn mock_mimc_sponge(inputs: &[ark_bn254::Fr]) -> ark_bn254::Fr {
+            use ark_ff::{PrimeField, Zero};
+            use ink_env::hash::Keccak256;
+
+            let mut constants = vec![ark_bn254::Fr::zero()];
+            let mut hash = [0u8; 32];
+            ink_env::hash_bytes::<Keccak256>(b"mimcsponge", &mut hash);
+            for _ in 1..219 {
+                let seed = hash;
+                ink_env::hash_bytes::<Keccak256>(&seed, &mut hash);
+                constants.push(ark_bn254::Fr::from_be_bytes_mod_order(&hash));
+            }
+
+            let mut left = ark_bn254::Fr::zero();
+            let mut right = ark_bn254::Fr::zero();
+            for input in inputs {
+                left += input;
+                for constant in &constants {
+                    let new_left = pow5(left + constant) + right;
+                    right = left;
+                    left = new_left;
+                }
+                // The last round has no constant and keeps the order.
+                right += pow5(left);
+            }
+            left
+        }
+
+        /// Returns the Poseidon hash of one or two field elements with the parameters of
+        /// circomlib: 8 full rounds, 56 or 57 partial rounds, and round constants and MDS matrix
+        /// generated by the Grain LFSR of the Poseidon paper.
+        #[cfg(feature = "chain-extension")]
+        fn mock_poseidon(inputs: &[ark_bn254::Fr]) -> Option<ark_bn254::Fr> {
+            use ark_ff::{Field, PrimeField, Zero};
+
+            let width = inputs.len() + 1;
+            let full_rounds = 8;
+            let partial_rounds = *[56, 57].get(inputs.len().checked_sub(1)?)?;
+            let mut grain = Grain::new(width, full_rounds, partial_rounds);
+            let constants: Vec<ark_bn254::Fr> = (0..(full_rounds + partial_rounds) * width)
+                .map(|_| grain.field_element())
+                .collect();
+            // The MDS matrix is the Cauchy matrix 1 / (x_i + y_j).
+            let points: Vec<ark_bn254::Fr> = (0..2 * width)
+                .map(|_| ark_bn254::Fr::from_be_bytes_mod_order(&grain.bits()))
+                .collect();
+            let mds: Vec<Vec<ark_bn254::Fr>> = (0..width)
+                .map(|i| {
+                    (0..width)
+                        .map(|j| (points[i] + points[width + j]).inverse().unwrap())
+                        .collect()
+                })
+                .collect();
+
+            let mut state = vec![ark_bn254::Fr::zero()];
+            state.extend_from_slice(inputs);
+            for round in 0..full_rounds + partial_rounds {
+                for (element, constant) in state.iter_mut().zip(&constants[round * width..]) {
+                    *element += constant;
+                }
+                if round < full_rounds / 2 || round >= full_rounds / 2 + partial_rounds {
+                    for element in state.iter_mut() {
+                        *element = pow5(*element);
+                    }
+                } else {
+                    state[0] = pow5(state[0]);
+                }
+                state = mds
+                    .iter()
+                    .map(|row| {
+                        row.iter()
+                            .zip(&state)
+                            .fold(ark_bn254::Fr::zero(), |sum, (m, x)| sum + *m * x)
+                    })
+                    .collect();
+            }
+            Some(state[0])
+        }
+
+        /// The Grain LFSR generating the Poseidon parameters.
+        #[cfg(feature = "chain-extension")]
+        struct Grain {
+            state: Vec<bool>,
+        }
+
+        #[cfg(feature = "chain-extension")]
+        impl Grain {
+            /// Initializes the LFSR with the field, S-box and rounds, and discards 160 bits.
+            fn new(width: usize, full_rounds: usize, partial_rounds: usize) -> Self {
+                let mut state = Vec::new();
+                let parameters = [
+                    (1, 2),
+                    (0, 4),
+                    (254, 12),
+                    (width, 12),
+                    (full_rounds, 10),
+                    (partial_rounds, 10),
+                    ((1 << 30) - 1, 30),
+                ];
+                for &(value, bits) in parameters.iter() {
+                    state.extend((0..bits).rev().map(|bit| (value >> bit) & 1 == 1));
+                }
+                let mut grain = Self { state };
+                for _ in 0..160 {
+                    grain.next_bit();
+                }
+                grain
+            }
+
+            fn next_bit(&mut self) -> bool {
+                let state = &self.state;
+                let bit = state[62] ^ state[51] ^ state[38] ^ state[23] ^ state[13] ^ state[0];
+                self.state.remove(0);
+                self.state.push(bit);
+                bit
+            }
+
+            /// Returns the second bit of the first pair of bits whose first bit is set.
+            fn filtered_bit(&mut self) -> bool {
+                loop {
+                    let keep = self.next_bit();
+                    let bit = self.next_bit();
+                    if keep {
+                        return bit;
+                    }
+                }
+            }
+
+            /// Returns 254 bits in 32 big-endian bytes.
+            fn bits(&mut self) -> [u8; 32] {
+                let mut bytes = [0u8; 32];
+                for index in 2..256 {
+                    if self.filtered_bit() {
+                        bytes[index / 8] |= 0x80 >> (index % 8);
+                    }
+                }
+                bytes
+            }
+
+            /// Returns the next 254 bits which are below the modulus.
+            fn field_element(&mut self) -> ark_bn254::Fr {
+                loop {
+                    let mut bytes = self.bits();
+                    bytes.reverse();
+                    if let Ok(element) = proof::decode_fr::<ark_bn254::Bn254>(&bytes) {
+                        return element;
+                    }
+                }
+            }
+        }
+
+        /// Returns the proof and public inputs of `test_verify`, packed for `verify_circuit`.
+        fn withdraw_proof() -> (EncodedProof, Vec<[u8; 32]>) {
+            (
+                encode_proof(&hex::decode(PROOF).unwrap()),
+                withdraw_inputs(ROOT, NULLIFIER_HASH, FEE, REFUND),
+            )
+        }
+
+        /// Packs the public inputs of the withdraw circuit for `verify_circuit`.
+        fn withdraw_inputs(
+            root: &str,
+            nullifier_hash: &str,
+            fee: u128,
+            refund: u128,
+        ) -> Vec<[u8; 32]> {
+            let mut root = hex::decode(root).unwrap();
+            root.reverse();
+            let mut nullifier_hash = hex::decode(nullifier_hash).unwrap();
+            nullifier_hash.reverse();
+            let inputs = ([
+                root,
+                nullifier_hash,
+                Verifier::buff2input(&RECIPIENT),
+                Verifier::buff2input(&RELAYER),
+                ([(fee).to_le_bytes(), [0u8; 16]]).concat(),
+                ([(refund).to_le_bytes(), [0u8; 16]]).concat(),
+            ])
+            .concat();
+
+            inputs
+                .chunks(32)
+                .map(|input| input.try_into().unwrap())
+                .collect()
+        }
+
+        /// Packs a small public input for `verify_circuit`.
+        fn small_input(value: u64) -> [u8; 32] {
+            let mut input = [0u8; 32];
+            input[..8].copy_from_slice(&value.to_le_bytes());
+            input
+        }
+
+        #[cfg(not(feature = "legacy-hex-abi"))]
+        fn encode_proof(proof: &[u8]) -> EncodedProof {
+            proof.to_vec()
+        }
+
+        #[cfg(feature = "legacy-hex-abi")]
+        fn encode_proof(proof: &[u8]) -> EncodedProof {
+            hex::encode(proof)
+        }
+
+        /// Encodes a big-endian hex public input.
+        #[cfg(not(feature = "legacy-hex-abi"))]
+        fn encode_input(input: &str) -> EncodedInput {
+            let mut input = hex::decode(input).unwrap();
+            input.reverse();
+            input.try_into().unwrap()
+        }
+
+        /// Encodes a big-endian hex public input.
+        #[cfg(feature = "legacy-hex-abi")]
+        fn encode_input(input: &str) -> EncodedInput {
+            String::from(input)
         }
     }
 }
@@ -0,0 +1,359 @@
+//! Verification of PLONK proofs over BN254.
+//!
+//! Proofs follow the PLONK paper of Gabizon, Williamson and Ciobotaru, with KZG commitments to
+//! the polynomials. The commitment key comes from a universal setup, the powers `[x^i]` of a
+//! secret `x`, so a new circuit only needs its verification key derived from the same powers
+//! rather than a ceremony of its own.
+//!
+//! A verification key has 23 hex encoded field elements: the commitments to the selector
+//! polynomials `q_m`, `q_l`, `q_r`, `q_o`, `q_c` and to the permutation polynomials `s_1`, `s_2`,
+//! `s_3`, the G2 point `[x]` of the setup, the domain size `n`, which is a power of two, and the
+//! coset shifts `k_1` and `k_2` of the permutation argument. The commitment to a zero polynomial,
+//! the point at infinity, is encoded as `(0, 0)`.
+//!
+//! A proof is encoded as the commitments `a`, `b`, `c`, `z`, `t_lo`, `t_mid`, `t_hi`, `w_zeta`
+//! and `w_zeta_omega`, each in the encoding of G1 points of `proof`, followed by the evaluations
+//! `a(zeta)`, `b(zeta)`, `c(zeta)`, `s_1(zeta)`, `s_2(zeta)` and `z(zeta * omega)`, each in 32
+//! little-endian bytes. The challenges are derived by Blake2x256 from the verification key, the
+//! public inputs and the proof in this order.
+//!
+//! The public inputs are the values of the `a` wire of the first gates, and the opening proofs of
+//! a batch are checked in a single pairing.
+
+use crate::batch::{decode_fq_hex, random_scalar};
+use crate::proof::{decode_fr, decode_g1, Engine};
+use crate::verifier::{VerificationKey, VerifierError};
+use ark_bn254::{Bn254, Fq2, Fr, G1Affine, G1Projective, G2Affine};
+use ark_ec::{AffineCurve, ProjectiveCurve};
+use ark_ff::{FftField, Field, One, PrimeField, ToBytes, Zero};
+use core::convert::TryInto;
+use ink_env::hash::Blake2x256;
+use ink_prelude::vec::Vec;
+
+/// The number of elements of a verification key.
+pub const VK_LENGTH: usize = 23;
+
+/// The length of an encoded G1 point.
+const G1_LENGTH: usize = 65;
+
+/// The length of an encoded proof: 9 G1 points and 6 evaluations.
+pub const PROOF_LENGTH: usize = 9 * G1_LENGTH + 6 * 32;
+
+/// A verification key with decoded points.
+pub struct PreparedVerificationKey {
+    /// The commitments to `q_m`, `q_l`, `q_r`, `q_o`, `q_c`, `s_1`, `s_2` and `s_3`.
+    commitments: [G1Affine; 8],
+    x: G2Affine,
+    /// The domain size.
+    pub n: u64,
+    k1: Fr,
+    k2: Fr,
+    omega: Fr,
+}
+
+impl PreparedVerificationKey {
+    /// Decodes the hex encoded elements of a verification key.
+    pub fn decode(verification_key: &VerificationKey) -> Result<Self, VerifierError> {
+        let vk = &verification_key.vk;
+        if vk.len() != VK_LENGTH || !verification_key.gamma_abc.is_empty() {
+            return Err(VerifierError::PairingFailed);
+        }
+        let coordinates = vk[..20]
+            .iter()
+            .map(|element| decode_fq_hex::<Bn254>(element))
+            .collect::<Result<Vec<_>, _>>()?;
+        let scalars = vk[20..]
+            .iter()
+            .map(|element| decode_fr_hex(element))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let commitments = coordinates[..16]
+            .chunks(2)
+            .map(|point| {
+                let point = match point {
+                    [x, y] if x.is_zero() && y.is_zero() => G1Affine::zero(),
+                    _ => G1Affine::new(point[0], point[1], false),
+                };
+                if point.is_on_curve() {
+                    Ok(point)
+                } else {
+                    Err(VerifierError::PairingFailed)
+                }
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let x = G2Affine::new(
+            Fq2::new(coordinates[16], coordinates[17]),
+            Fq2::new(coordinates[18], coordinates[19]),
+            false,
+        );
+        if !x.is_on_curve() || !x.is_in_correct_subgroup_assuming_on_curve() {
+            return Err(VerifierError::PairingFailed);
+        }
+
+        // The domain size must be a power of two with a root of unity of its order.
+        let n = match scalars[0].into_repr().as_ref() {
+            [n, 0, 0, 0] if n.is_power_of_two() => *n,
+            _ => return Err(VerifierError::PairingFailed),
+        };
+        let omega = Fr::get_root_of_unity(n as usize).ok_or(VerifierError::PairingFailed)?;
+        Ok(Self {
+            commitments: commitments.try_into().unwrap(),
+            x,
+            n,
+            k1: scalars[1],
+            k2: scalars[2],
+            omega,
+        })
+    }
+}
+
+/// A PLONK proof.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Proof {
+    pub a: G1Affine,
+    pub b: G1Affine,
+    pub c: G1Affine,
+    pub z: G1Affine,
+    pub t_lo: G1Affine,
+    pub t_mid: G1Affine,
+    pub t_hi: G1Affine,
+    pub w_zeta: G1Affine,
+    pub w_zeta_omega: G1Affine,
+    pub a_zeta: Fr,
+    pub b_zeta: Fr,
+    pub c_zeta: Fr,
+    pub s1_zeta: Fr,
+    pub s2_zeta: Fr,
+    pub z_zeta_omega: Fr,
+}
+
+impl Proof {
+    /// Decodes a proof, and checks that its points are on the curve and its evaluations in the
+    /// scalar field.
+    pub fn decode(bytes: &[u8]) -> Result<Self, VerifierError> {
+        if bytes.len() != PROOF_LENGTH {
+            return Err(VerifierError::BadProofLength);
+        }
+        let (points, evaluations) = bytes.split_at(9 * G1_LENGTH);
+        let points = points
+            .chunks(G1_LENGTH)
+            .map(decode_g1::<Bn254>)
+            .collect::<Result<Vec<_>, _>>()?;
+        let evaluations = evaluations
+            .chunks(32)
+            .map(|evaluation| decode_fr::<Bn254>(evaluation.try_into().unwrap()))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self {
+            a: points[0],
+            b: points[1],
+            c: points[2],
+            z: points[3],
+            t_lo: points[4],
+            t_mid: points[5],
+            t_hi: points[6],
+            w_zeta: points[7],
+            w_zeta_omega: points[8],
+            a_zeta: evaluations[0],
+            b_zeta: evaluations[1],
+            c_zeta: evaluations[2],
+            s1_zeta: evaluations[3],
+            s2_zeta: evaluations[4],
+            z_zeta_omega: evaluations[5],
+        })
+    }
+}
+
+/// Decodes the encoded proofs and public inputs, and verifies them against the verification key.
+///
+/// Fails if a proof has more public inputs than the domain has gates.
+pub fn decode_and_verify(
+    verification_key: &VerificationKey,
+    batch: &[(Vec<u8>, Vec<[u8; 32]>)],
+    seed: &[u8; 32],
+) -> Result<bool, VerifierError> {
+    let proofs = batch
+        .iter()
+        .map(|(proof, public_inputs)| {
+            let public_inputs = public_inputs
+                .iter()
+                .map(decode_fr::<Bn254>)
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok((Proof::decode(proof)?, public_inputs))
+        })
+        .collect::<Result<Vec<_>, VerifierError>>()?;
+    verify_batch(verification_key, &proofs, seed)
+}
+
+/// Verifies the proofs with their public inputs against the verification key.
+///
+/// The opening proofs are combined with random scalars derived from the seed, so the batch
+/// takes one pairing check. Returns `true` if every proof verifies.
+pub fn verify_batch(
+    verification_key: &VerificationKey,
+    batch: &[(Proof, Vec<Fr>)],
+    seed: &[u8; 32],
+) -> Result<bool, VerifierError> {
+    let vk = PreparedVerificationKey::decode(verification_key)?;
+
+    let mut left = G1Projective::zero();
+    let mut right = G1Projective::zero();
+    for (index, (proof, public_inputs)) in batch.iter().enumerate() {
+        if public_inputs.len() as u64 > vk.n {
+            return Err(VerifierError::BadInputLength);
+        }
+        let (proof_left, proof_right) = opening(&vk, proof, public_inputs)?;
+        let r: Fr = random_scalar(seed, index as u32);
+        left += &proof_left.mul(r.into_repr());
+        right += &proof_right.mul(r.into_repr());
+    }
+
+    // e(left, [x]) = e(right, [1])
+    Bn254::pairing_check(&[
+        (left.into_affine(), vk.x),
+        ((-right).into_affine(), G2Affine::prime_subgroup_generator()),
+    ])
+}
+
+/// Returns the sides of the pairing equation of the opening proofs, `w_zeta + u * w_zeta_omega`
+/// and `zeta * w_zeta + u * zeta * omega * w_zeta_omega + F - E` in the notation of the paper.
+fn opening(
+    vk: &PreparedVerificationKey,
+    proof: &Proof,
+    public_inputs: &[Fr],
+) -> Result<(G1Projective, G1Projective), VerifierError> {
+    let mut transcript = Transcript::default();
+    for commitment in vk.commitments.iter() {
+        transcript.append(commitment);
+    }
+    transcript.append(&Fr::from(vk.n));
+    transcript.append(&vk.k1);
+    transcript.append(&vk.k2);
+    for input in public_inputs {
+        transcript.append(input);
+    }
+    transcript.append(&proof.a);
+    transcript.append(&proof.b);
+    transcript.append(&proof.c);
+    let beta = transcript.challenge();
+    let gamma = transcript.challenge();
+    transcript.append(&proof.z);
+    let alpha = transcript.challenge();
+    transcript.append(&proof.t_lo);
+    transcript.append(&proof.t_mid);
+    transcript.append(&proof.t_hi);
+    let zeta = transcript.challenge();
+    for evaluation in [
+        proof.a_zeta,
+        proof.b_zeta,
+        proof.c_zeta,
+        proof.s1_zeta,
+        proof.s2_zeta,
+        proof.z_zeta_omega,
+    ]
+    .iter()
+    {
+        transcript.append(evaluation);
+    }
+    let v = transcript.challenge();
+    transcript.append(&proof.w_zeta);
+    transcript.append(&proof.w_zeta_omega);
+    let u = transcript.challenge();
+
+    // The vanishing polynomial and the Lagrange polynomials L_i(X) = omega^i (X^n - 1) /
+    // (n (X - omega^i)) at zeta.
+    let zeta_n = zeta.pow([vk.n]);
+    let vanishing = zeta_n - Fr::one();
+    let lagrange = |omega_i: Fr| -> Result<Fr, VerifierError> {
+        let denominator = (Fr::from(vk.n) * (zeta - omega_i))
+            .inverse()
+            .ok_or(VerifierError::PairingFailed)?;
+        Ok(omega_i * vanishing * denominator)
+    };
+    let l_0 = lagrange(Fr::one())?;
+    let mut public_input = Fr::zero();
+    let mut omega_i = Fr::one();
+    for input in public_inputs {
+        public_input -= &(*input * lagrange(omega_i)?);
+        omega_i *= &vk.omega;
+    }
+
+    let a = proof.a_zeta + gamma;
+    let b = proof.b_zeta + gamma;
+    let c = proof.c_zeta + gamma;
+    let permutation = (a + beta * proof.s1_zeta) * (b + beta * proof.s2_zeta);
+    let alpha_squared = alpha.square();
+    let r_0 = public_input - l_0 * alpha_squared - alpha * permutation * c * proof.z_zeta_omega;
+
+    let [q_m, q_l, q_r, q_o, q_c, s_1, s_2, s_3] = vk.commitments;
+    let z_scalar =
+        alpha * (a + beta * zeta) * (b + beta * vk.k1 * zeta) * (c + beta * vk.k2 * zeta)
+            + l_0 * alpha_squared
+            + u;
+    let t = proof.t_lo.into_projective()
+        + proof.t_mid.mul(zeta_n.into_repr())
+        + proof.t_hi.mul(zeta_n.square().into_repr());
+    let d = q_m.mul((proof.a_zeta * proof.b_zeta).into_repr())
+        + q_l.mul(proof.a_zeta.into_repr())
+        + q_r.mul(proof.b_zeta.into_repr())
+        + q_o.mul(proof.c_zeta.into_repr())
+        + q_c.into_projective()
+        + proof.z.mul(z_scalar.into_repr())
+        - s_3.mul((alpha * beta * permutation * proof.z_zeta_omega).into_repr())
+        - t.mul(vanishing.into_repr());
+
+    let mut f = d;
+    let mut e = -r_0 + u * proof.z_zeta_omega;
+    let mut v_i = Fr::one();
+    for (commitment, evaluation) in [
+        (proof.a, proof.a_zeta),
+        (proof.b, proof.b_zeta),
+        (proof.c, proof.c_zeta),
+        (s_1, proof.s1_zeta),
+        (s_2, proof.s2_zeta),
+    ]
+    .iter()
+    {
+        v_i *= &v;
+        f += &commitment.mul(v_i.into_repr());
+        e += &(v_i * evaluation);
+    }
+
+    let left = proof.w_zeta.into_projective() + proof.w_zeta_omega.mul(u.into_repr());
+    let right = proof.w_zeta.mul(zeta.into_repr())
+        + proof.w_zeta_omega.mul((u * zeta * vk.omega).into_repr())
+        + f
+        - G1Affine::prime_subgroup_generator().mul(e.into_repr());
+    Ok((left, right))
+}
+
+/// A Fiat-Shamir transcript, which hashes everything appended since the last challenge.
+#[derive(Default)]
+struct Transcript {
+    bytes: Vec<u8>,
+}
+
+impl Transcript {
+    /// Appends a point or a field element in its proof encoding.
+    fn append<T: ToBytes>(&mut self, element: &T) {
+        element
+            .write(&mut self.bytes)
+            .expect("writing to a vector never fails");
+    }
+
+    /// Returns the next challenge, and starts the transcript over from it.
+    fn challenge(&mut self) -> Fr {
+        let mut hash = [0u8; 32];
+        ink_env::hash_bytes::<Blake2x256>(&self.bytes, &mut hash);
+        self.bytes = hash.to_vec();
+        Fr::from_le_bytes_mod_order(&hash)
+    }
+}
+
+/// Decodes a big-endian hex scalar of the verification key.
+fn decode_fr_hex(element: &str) -> Result<Fr, VerifierError> {
+    let mut bytes = [0u8; 32];
+    hex::decode_to_slice(element.trim_start_matches("0x"), &mut bytes)
+        .map_err(|_| VerifierError::PairingFailed)?;
+    bytes.reverse();
+    decode_fr::<Bn254>(&bytes).map_err(|_| VerifierError::PairingFailed)
+}
@@ -0,0 +1,224 @@
+//! Decoding of groth16 proofs.
+//!
+//! A proof is encoded as generated by `scripts/2-generate-proof.js`: the points `a`, `b` and `c`,
+//! every coordinate in little-endian bytes, and each point followed by its infinity flag.
+//! Coordinates take 32 bytes over BN254, and 48 bytes over BLS12-381.
+//!
+//! With the `legacy-hex-abi` feature, the verifier takes proofs and the public inputs of the
+//! withdraw circuit as hex strings, as it did before taking bytes.
+
+use crate::verifier::VerifierError;
+use ark_bls12_381::Bls12_381;
+use ark_bn254::Bn254;
+use ark_ec::{short_weierstrass_jacobian::GroupAffine, PairingEngine, SWModelParameters};
+use ark_ff::{FromBytes, One, PrimeField};
+#[cfg(feature = "legacy-hex-abi")]
+use core::convert::TryInto;
+#[cfg(feature = "legacy-hex-abi")]
+use ink_prelude::string::String;
+use ink_prelude::vec::Vec;
+
+/// An encoded proof, as passed to the messages of the verifier.
+#[cfg(not(feature = "legacy-hex-abi"))]
+pub type EncodedProof = Vec<u8>;
+
+/// A hex encoded proof, as passed to the messages of the verifier.
+#[cfg(feature = "legacy-hex-abi")]
+pub type EncodedProof = String;
+
+/// A public input of the withdraw circuit in 32 little-endian bytes, as passed to `verify`.
+#[cfg(not(feature = "legacy-hex-abi"))]
+pub type EncodedInput = [u8; 32];
+
+/// A public input of the withdraw circuit as big-endian hex, as passed to `verify`.
+#[cfg(feature = "legacy-hex-abi")]
+pub type EncodedInput = String;
+
+/// A pairing engine the verifier supports.
+pub trait Engine: PairingEngine {
+    /// The length of an encoded coordinate.
+    const FIELD_LENGTH: usize;
+
+    /// Returns the G1 point, if it is on the curve and in the prime order subgroup.
+    fn g1(x: Self::Fq, y: Self::Fq, infinity: bool) -> Result<Self::G1Affine, VerifierError>;
+
+    /// Returns the G2 point, if it is on the curve and in the prime order subgroup.
+    fn g2(x: Self::Fqe, y: Self::Fqe, infinity: bool) -> Result<Self::G2Affine, VerifierError>;
+
+    /// Returns the element `c0 + c1 * u` of the quadratic extension hosting G2.
+    fn fq2(c0: Self::Fq, c1: Self::Fq) -> Self::Fqe;
+
+    /// Returns whether the product of the pairings of the points is one.
+    fn pairing_check(pairs: &[(Self::G1Affine, Self::G2Affine)]) -> Result<bool, VerifierError> {
+        pairing_check::<Self>(pairs)
+    }
+}
+
+impl Engine for Bn254 {
+    const FIELD_LENGTH: usize = 32;
+
+    fn g1(x: Self::Fq, y: Self::Fq, infinity: bool) -> Result<Self::G1Affine, VerifierError> {
+        check_point(ark_bn254::G1Affine::new(x, y, infinity))
+    }
+
+    fn g2(x: Self::Fqe, y: Self::Fqe, infinity: bool) -> Result<Self::G2Affine, VerifierError> {
+        check_point(ark_bn254::G2Affine::new(x, y, infinity))
+    }
+
+    fn fq2(c0: Self::Fq, c1: Self::Fq) -> Self::Fqe {
+        ark_bn254::Fq2::new(c0, c1)
+    }
+
+    /// Checks the pairings by the chain extension of the runtime rather than in Wasm.
+    #[cfg(feature = "chain-extension")]
+    fn pairing_check(pairs: &[(Self::G1Affine, Self::G2Affine)]) -> Result<bool, VerifierError> {
+        crate::extension::bn254_pairing_check(pairs)
+    }
+}
+
+impl Engine for Bls12_381 {
+    const FIELD_LENGTH: usize = 48;
+
+    fn g1(x: Self::Fq, y: Self::Fq, infinity: bool) -> Result<Self::G1Affine, VerifierError> {
+        check_point(ark_bls12_381::G1Affine::new(x, y, infinity))
+    }
+
+    fn g2(x: Self::Fqe, y: Self::Fqe, infinity: bool) -> Result<Self::G2Affine, VerifierError> {
+        check_point(ark_bls12_381::G2Affine::new(x, y, infinity))
+    }
+
+    fn fq2(c0: Self::Fq, c1: Self::Fq) -> Self::Fqe {
+        ark_bls12_381::Fq2::new(c0, c1)
+    }
+}
+
+/// A groth16 proof.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Proof<E: Engine> {
+    pub a: E::G1Affine,
+    pub b: E::G2Affine,
+    pub c: E::G1Affine,
+}
+
+impl<E: Engine> Proof<E> {
+    /// The length of an encoded proof: `a` and `c` with 2 coordinates each, `b` with 4, and
+    /// the infinity flags.
+    pub const LENGTH: usize = 8 * E::FIELD_LENGTH + 3;
+
+    /// Decodes a proof, and checks that its points are on the curve and in the prime order subgroup.
+    pub fn decode(bytes: &[u8]) -> Result<Self, VerifierError> {
+        if bytes.len() != Self::LENGTH {
+            return Err(VerifierError::BadProofLength);
+        }
+        let length = E::FIELD_LENGTH;
+        Ok(Self {
+            a: decode_g1::<E>(&bytes[..2 * length + 1])?,
+            b: decode_g2::<E>(&bytes[2 * length + 1..6 * length + 2])?,
+            c: decode_g1::<E>(&bytes[6 * length + 2..])?,
+        })
+    }
+}
+
+/// Returns the bytes of an encoded proof.
+#[cfg(not(feature = "legacy-hex-abi"))]
+pub fn decode_proof(proof: EncodedProof) -> Result<Vec<u8>, VerifierError> {
+    Ok(proof)
+}
+
+/// Returns the bytes of a hex encoded proof.
+#[cfg(feature = "legacy-hex-abi")]
+pub fn decode_proof(proof: EncodedProof) -> Result<Vec<u8>, VerifierError> {
+    hex::decode(proof).map_err(|_| VerifierError::InvalidHex)
+}
+
+/// Returns the little-endian bytes of an encoded public input.
+#[cfg(not(feature = "legacy-hex-abi"))]
+pub fn decode_input(input: EncodedInput) -> Result<[u8; 32], VerifierError> {
+    Ok(input)
+}
+
+/// Returns the little-endian bytes of a hex encoded public input.
+#[cfg(feature = "legacy-hex-abi")]
+pub fn decode_input(input: EncodedInput) -> Result<[u8; 32], VerifierError> {
+    let mut input: [u8; 32] = hex::decode(input)
+        .map_err(|_| VerifierError::InvalidHex)?
+        .try_into()
+        .map_err(|_| VerifierError::InputNotInField)?;
+    input.reverse();
+    Ok(input)
+}
+
+/// Decodes a G1 point of `2 * FIELD_LENGTH + 1` bytes, and checks that it is on the curve and in
+/// the prime order subgroup.
+pub fn decode_g1<E: Engine>(bytes: &[u8]) -> Result<E::G1Affine, VerifierError> {
+    let length = E::FIELD_LENGTH;
+    E::g1(
+        decode_fq::<E>(&bytes[..length])?,
+        decode_fq::<E>(&bytes[length..2 * length])?,
+        decode_infinity(bytes[2 * length])?,
+    )
+}
+
+/// Decodes a G2 point of `4 * FIELD_LENGTH + 1` bytes, and checks that it is on the curve and in
+/// the prime order subgroup.
+pub fn decode_g2<E: Engine>(bytes: &[u8]) -> Result<E::G2Affine, VerifierError> {
+    let length = E::FIELD_LENGTH;
+    let field = |index: usize| decode_fq::<E>(&bytes[index * length..(index + 1) * length]);
+    E::g2(
+        E::fq2(field(0)?, field(1)?),
+        E::fq2(field(2)?, field(3)?),
+        decode_infinity(bytes[4 * length])?,
+    )
+}
+
+/// Returns whether the product of the pairings of the points is one, computed in Wasm.
+pub fn pairing_check<E: PairingEngine>(
+    pairs: &[(E::G1Affine, E::G2Affine)],
+) -> Result<bool, VerifierError> {
+    let pairs: Vec<(E::G1Prepared, E::G2Prepared)> = pairs
+        .iter()
+        .map(|(g1, g2)| ((*g1).into(), (*g2).into()))
+        .collect();
+    let result =
+        E::final_exponentiation(&E::miller_loop(&pairs)).ok_or(VerifierError::PairingFailed)?;
+    Ok(result.is_one())
+}
+
+/// Decodes a public input, which must be below the scalar field modulus.
+pub fn decode_fr<E: Engine>(bytes: &[u8; 32]) -> Result<E::Fr, VerifierError> {
+    decode_field(bytes).ok_or(VerifierError::InputNotInField)
+}
+
+/// Decodes a coordinate, which must be below the base field modulus.
+pub fn decode_fq<E: Engine>(bytes: &[u8]) -> Result<E::Fq, VerifierError> {
+    decode_field(bytes).ok_or(VerifierError::PointNotOnCurve)
+}
+
+/// Decodes a little-endian field element, if it has the length of the modulus and is below it.
+fn decode_field<F: PrimeField>(mut bytes: &[u8]) -> Option<F> {
+    let repr = F::BigInt::read(&mut bytes).ok()?;
+    if !bytes.is_empty() {
+        return None;
+    }
+    F::from_repr(repr)
+}
+
+fn check_point<P: SWModelParameters>(
+    point: GroupAffine<P>,
+) -> Result<GroupAffine<P>, VerifierError> {
+    if !point.is_on_curve() {
+        return Err(VerifierError::PointNotOnCurve);
+    }
+    if !point.is_in_correct_subgroup_assuming_on_curve() {
+        return Err(VerifierError::PointNotInSubgroup);
+    }
+    Ok(point)
+}
+
+fn decode_infinity(flag: u8) -> Result<bool, VerifierError> {
+    match flag {
+        0 => Ok(false),
+        1 => Ok(true),
+        _ => Err(VerifierError::PointNotOnCurve),
+    }
+}